@@ -0,0 +1,39 @@
+//! The throughput of monitors over synthetic event stream.
+//!
+//! Run by `cargo bench`, the rate of processed events is printed for each
+//! monitor. Like the hidden `bench` command, it drives monitors without
+//! connecting to niri, but through the public testing fixtures.
+
+use niri_glue::{testing, Config, Format};
+use std::{hint::black_box, time::Instant};
+
+/// The count of events pushed through each monitor
+const EVENTS: usize = 100_000;
+
+/// The count of windows in synthetic state
+const WINDOWS: u64 = 50;
+
+fn main() {
+    let config = Config::default();
+    let monitors: [&[&str]; 6] = [
+        &["layout"],
+        &["pager"],
+        &["workspaces"],
+        &["windows"],
+        &["window"],
+        &["status"],
+    ];
+    for args in monitors {
+        for format in [Format::Waybar, Format::Json] {
+            let events = testing::state(WINDOWS)
+                .into_iter()
+                .chain((0..EVENTS).map(|i| testing::event(i, WINDOWS)));
+            let start = Instant::now();
+            let lines = testing::render(args, format.clone(), &config, events);
+            let elapsed = start.elapsed();
+            black_box(lines);
+            let rate = EVENTS as f64 / elapsed.as_secs_f64();
+            println!("{} {format:?}: {rate:.0} events/s", args.join(" "));
+        }
+    }
+}
@@ -0,0 +1,1020 @@
+//! One-shot commands wrapping niri actions on windows and compositor.
+
+use crate::{
+    exit::{self, Code},
+    ipc, jsonl, query, waybar, yambar, Config, Format, Niri, Runner,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use niri_ipc::{
+    Action, Request, Response, SizeChange, Window, Workspace,
+    WorkspaceReferenceArg,
+};
+use std::{
+    convert::Infallible,
+    env, fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Window fullscreen toggle.
+///
+/// Toggles fullscreen of the focused window or of the window with given id.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct ToggleFullscreen {
+    /// Id of window to toggle, the focused window by default
+    id: Option<u64>,
+}
+
+/// Window closer.
+///
+/// Closes the focused window or the window with given id. Intended for
+/// middle-click handlers of taskbars.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct CloseWindow {
+    /// Id of window to close, the focused window by default
+    id: Option<u64>,
+
+    /// Close window only if it has the given application id
+    ///
+    /// Guards against closing the wrong window when focus changed since the
+    /// handler was triggered.
+    #[arg(long)]
+    app_id: Option<String>,
+}
+
+/// Niri exit.
+///
+/// Asks for confirmation in terminal, or lets niri ask for it when not run
+/// from terminal, and exits niri. Intended for power menus.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Quit {
+    /// Exit without confirmation
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Close all windows and wait for them to exit before exiting niri
+    ///
+    /// Gives applications the chance to save their state. Niri is not
+    /// exited if some windows are still open after `--wait` seconds.
+    #[arg(short, long)]
+    graceful: bool,
+
+    /// Seconds to wait for windows to close in graceful mode
+    #[arg(long, value_name = "SECS", default_value_t = 10)]
+    wait: u64,
+}
+
+/// Monitors power switch.
+///
+/// Powers monitors off or on. Niri does not report whether monitors are
+/// powered, so toggling relies on the state set by the last run of this
+/// command, kept in `$XDG_RUNTIME_DIR/niri-glue/monitors`.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Monitors {
+    /// The power state to switch to
+    power: Power,
+}
+
+/// Column maximizer.
+///
+/// Maximizes the focused column. Niri itself restores the previous width on
+/// the repeated maximize, so the columns maximized by this command are
+/// remembered in `$XDG_RUNTIME_DIR/niri-glue/maximized` to make the repeated
+/// runs without `--toggle` harmless.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct MaximizeColumn {
+    /// Restore the previous width if column is maximized already
+    #[arg(short, long)]
+    toggle: bool,
+}
+
+/// Column centering.
+///
+/// Centers the focused column on the screen.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct CenterColumn {}
+
+/// Hotkey overlay.
+///
+/// Shows niri's overlay of hotkeys, for help buttons of bars.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct ShowHotkeyOverlay {}
+
+/// Workspaces overview toggle.
+///
+/// Toggles niri's overview of workspaces, for "Expo" buttons of bars and for
+/// gesture daemons. Requires niri 25.05 or newer.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct ToggleOverview {
+    /// Open the overview if it is closed, do nothing otherwise
+    #[arg(long, conflicts_with = "close")]
+    open: bool,
+
+    /// Close the overview if it is open, do nothing otherwise
+    #[arg(long)]
+    close: bool,
+}
+
+/// Screen transition.
+///
+/// Freezes the screen and crossfades to its new contents, for scripts
+/// switching themes or wallpapers. Run it right before the switch.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct ScreenTransition {
+    /// Milliseconds to keep the screen frozen before the transition
+    #[arg(short, long)]
+    delay_ms: Option<u16>,
+}
+
+/// Program launcher.
+///
+/// Spawns program by niri, so it runs in the environment of compositor
+/// rather than the one of caller. The arguments are passed as is, without
+/// shell. The environment and working directory are set with `env` utility.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Spawn {
+    /// Set environment variable of program, may be repeated
+    #[arg(short, long, value_name = "KEY=VAL", value_parser = parse_env)]
+    env: Vec<String>,
+
+    /// Working directory of program
+    #[arg(short, long)]
+    cwd: Option<PathBuf>,
+
+    /// Program with its arguments
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true
+    )]
+    command: Vec<String>,
+}
+
+fn parse_env(s: &str) -> Result<String, &'static str> {
+    match s.split_once('=') {
+        Some((key, _)) if !key.is_empty() => Ok(s.into()),
+        _ => Err("expected KEY=VAL"),
+    }
+}
+
+/// Arbitrary action.
+///
+/// Sends any niri action, including ones without dedicated command yet. The
+/// action is either JSON, like `{"FocusWindow":{"id":12}}`, or the name of
+/// action with `key=value` fields, like `focus-window id=12`. The values
+/// are JSON or, when they are not valid JSON, strings.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct SendAction {
+    /// JSON of action or name of action
+    action: String,
+
+    /// The fields of action given by name
+    #[arg(value_name = "KEY=VALUE")]
+    fields: Vec<String>,
+}
+
+/// Arbitrary request.
+///
+/// Sends any niri request and prints the reply in chosen format. The request
+/// is either JSON, like `"Windows"`, or the name of request with `key=value`
+/// fields, like `windows` or `output output=DP-1 action={"Off":null}`.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct SendRequest {
+    /// JSON of request or name of request
+    request: String,
+
+    /// The fields of request given by name
+    #[arg(value_name = "KEY=VALUE")]
+    fields: Vec<String>,
+}
+
+/// Batch of actions.
+///
+/// Reads actions from stdin one per line and sends them in order. Each line
+/// is either JSON of action or its name with `key=value` fields, like for
+/// `action` command. Empty lines and lines starting with `#` are skipped.
+/// Niri serves single request per connection, so each action is sent over
+/// its own one.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Batch {
+    /// Stop at the first failed action, the default
+    #[arg(long, overrides_with = "keep_going")]
+    stop_on_error: bool,
+
+    /// Send the rest of actions after failed one
+    #[arg(long = "continue", overrides_with = "stop_on_error")]
+    keep_going: bool,
+}
+
+/// The power state of monitors
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Power {
+    Off,
+    On,
+    Toggle,
+}
+
+/// Window mover between outputs.
+///
+/// Moves the focused window or the window with given id to the active
+/// workspace of another output.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct MoveWindowToOutput {
+    /// Target output
+    ///
+    /// One of `next`, `prev` or name of output. The outputs are ordered by
+    /// their position from left to right and top to bottom, with wraparound.
+    target: OutputTarget,
+
+    /// Id of window to move, the focused window by default
+    #[arg(long)]
+    id: Option<u64>,
+}
+
+/// Output focus switcher.
+///
+/// Focuses another output by name or relative to the focused one. The focus
+/// goes to the active workspace of output, for click handlers of bars on
+/// each output and keybinding scripts on multi-head setups.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct FocusOutput {
+    /// Target output
+    ///
+    /// One of `next`, `prev` or name of output, ordered the same way as for
+    /// `move-window-to-output`.
+    target: OutputTarget,
+}
+
+/// Workspace mover between outputs.
+///
+/// Moves the focused workspace to another output by name or relative to the
+/// current one. The focus stays on the current output unless `--follow` is
+/// given. Requires niri 25.02 or newer.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct MoveWorkspaceToOutput {
+    /// Target output
+    ///
+    /// One of `next`, `prev` or name of output, ordered the same way as for
+    /// `move-window-to-output`.
+    target: OutputTarget,
+
+    /// Keep the focus on the moved workspace
+    #[arg(short, long)]
+    follow: bool,
+}
+
+/// The target of moving between outputs
+#[derive(Debug, Clone)]
+enum OutputTarget {
+    Next,
+    Prev,
+    Name(String),
+}
+
+impl FromStr for OutputTarget {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "next" => Self::Next,
+            "prev" => Self::Prev,
+            s => Self::Name(s.into()),
+        })
+    }
+}
+
+/// Column builder.
+///
+/// Consumes the window to the right into the column of the focused window or
+/// expels the focused window from its column, for scripts building layouts
+/// without keybindings.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Column {
+    #[command(subcommand)]
+    action: ColumnAction,
+}
+
+/// The changes of columns
+#[derive(Subcommand, Debug, Clone)]
+enum ColumnAction {
+    /// Consume the window to the right into the column of window
+    Consume(ColumnWindow),
+    /// Expel the window from its column to the right
+    Expel(ColumnWindow),
+}
+
+/// The window to change column of
+#[derive(Args, Debug, Clone)]
+struct ColumnWindow {
+    /// Id of window to act on instead of the focused one
+    ///
+    /// The window is focused for the action, and the focus is returned to
+    /// the previously focused window afterwards.
+    #[arg(long)]
+    window_id: Option<u64>,
+}
+
+/// Tabbed column toggle.
+///
+/// Switches the focused column between normal and tabbed display. Requires
+/// niri 25.02 or newer.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct ToggleTabbed {}
+
+/// Column width switcher.
+///
+/// Changes the width of the focused column and prints the applied change,
+/// for on-screen display of keybindings or scrolling on bar. Niri does not
+/// report the resulting width, so the printed width is exact only when it
+/// is set absolutely.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct ColumnWidth {
+    /// The width change
+    ///
+    /// One of `preset` to switch to the next preset width, absolute width in
+    /// pixels or percents of output, like `800` or `50%`, or relative one,
+    /// like `-100` or `+5%`.
+    #[arg(allow_hyphen_values = true)]
+    width: WidthChange,
+}
+
+/// The change of column width
+#[derive(Debug, Clone)]
+enum WidthChange {
+    Preset,
+    Change(SizeChange),
+}
+
+impl FromStr for WidthChange {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preset" => Ok(Self::Preset),
+            s => s.parse().map(Self::Change),
+        }
+    }
+}
+
+impl WidthChange {
+    fn text(&self) -> String {
+        match self {
+            Self::Preset => "preset".into(),
+            Self::Change(SizeChange::SetFixed(px)) => px.to_string(),
+            Self::Change(SizeChange::SetProportion(p)) => format!("{p}%"),
+            Self::Change(SizeChange::AdjustFixed(px)) => format!("{px:+}"),
+            Self::Change(SizeChange::AdjustProportion(p)) => format!("{p:+}%"),
+        }
+    }
+}
+
+/// Hold-to-resize helper.
+///
+/// Keeps changing the width of the focused column and the height of the
+/// focused window by small steps until killed. Niri actions are one-shot, so
+/// the bindings resizing smoothly while key is held spawn this command on
+/// press and kill it on release.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Resize {
+    /// The step of column width
+    ///
+    /// Relative change in pixels or percents of output, like `-10` or `+1%`.
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        value_parser = parse_step,
+        required_unless_present = "height"
+    )]
+    width: Option<SizeChange>,
+
+    /// The step of window height, in format of `--width`
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_step)]
+    height: Option<SizeChange>,
+
+    /// Count of steps per second
+    #[arg(
+        short,
+        long,
+        default_value_t = 30,
+        value_parser = clap::value_parser!(u32).range(1..=1000)
+    )]
+    rate: u32,
+}
+
+fn parse_step(s: &str) -> Result<SizeChange, &'static str> {
+    match s.parse()? {
+        change @ (SizeChange::AdjustFixed(_)
+        | SizeChange::AdjustProportion(_)) => Ok(change),
+        _ => Err("step must be relative, like `+10` or `-1%`"),
+    }
+}
+
+impl Runner for ToggleFullscreen {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        niri.send(Request::Action(Action::FullscreenWindow { id: self.id }));
+    }
+}
+
+impl Runner for CloseWindow {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let id = match &self.app_id {
+            Some(app_id) => Some(guard(&niri, self.id, app_id)),
+            None => self.id,
+        };
+        niri.send(Request::Action(Action::CloseWindow { id }));
+    }
+}
+
+impl Runner for Quit {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let prompt = !self.yes && io::stdin().is_terminal();
+        if prompt && !confirm("Quit niri?") {
+            return;
+        }
+        if self.graceful {
+            if !self.yes && !prompt {
+                exit::fail(
+                    Code::Usage,
+                    "Graceful quit requires --yes outside of terminal",
+                );
+            }
+            close_all(&niri, Duration::from_secs(self.wait));
+        }
+        let skip_confirmation = self.yes || prompt;
+        niri.send(Request::Action(Action::Quit { skip_confirmation }));
+    }
+}
+
+impl Runner for Monitors {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let path = state_path("monitors");
+        let power = match self.power {
+            Power::Toggle => {
+                let state = path.as_ref().and_then(|p| fs::read(p).ok());
+                match state.as_deref() {
+                    Some(b"off") => Power::On,
+                    _ => Power::Off,
+                }
+            }
+            power => power,
+        };
+        let (action, state) = match power {
+            Power::Off => (Action::PowerOffMonitors {}, "off"),
+            _ => (Action::PowerOnMonitors {}, "on"),
+        };
+        niri.send(Request::Action(action));
+        if let Some(path) = path.filter(|_| !niri.dry_run()) {
+            write_state(&path, state.as_bytes());
+        }
+    }
+}
+
+impl Runner for MaximizeColumn {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let windows = windows(&niri);
+        let Some(focused) = windows.iter().find(|w| w.is_focused) else {
+            return;
+        };
+        let path = state_path("maximized");
+        let state = path.as_ref().and_then(|p| fs::read_to_string(p).ok());
+        // Only windows which are still open are kept
+        let mut maximized = state
+            .iter()
+            .flat_map(|state| state.lines())
+            .filter_map(|id| id.parse().ok())
+            .filter(|id| windows.iter().any(|w| w.id == *id))
+            .collect::<Vec<u64>>();
+        match maximized.iter().position(|id| *id == focused.id) {
+            Some(_) if !self.toggle => return,
+            Some(pos) => {
+                maximized.remove(pos);
+            }
+            None => maximized.push(focused.id),
+        }
+        niri.send(Request::Action(Action::MaximizeColumn {}));
+        if let Some(path) = path.filter(|_| !niri.dry_run()) {
+            let state = maximized
+                .iter()
+                .map(|id| format!("{id}\n"))
+                .collect::<String>();
+            write_state(&path, state.as_bytes());
+        }
+    }
+}
+
+impl Runner for CenterColumn {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        niri.send(Request::Action(Action::CenterColumn {}));
+    }
+}
+
+impl Runner for ShowHotkeyOverlay {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        niri.send(Request::Action(Action::ShowHotkeyOverlay {}));
+    }
+}
+
+impl Runner for ToggleOverview {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let action = if self.open {
+            ipc::Action::OpenOverview {}
+        } else if self.close {
+            ipc::Action::CloseOverview {}
+        } else {
+            ipc::Action::ToggleOverview {}
+        };
+        niri.send_action(action);
+    }
+}
+
+impl Runner for ScreenTransition {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let delay_ms = self.delay_ms;
+        niri.send(Request::Action(Action::DoScreenTransition { delay_ms }));
+    }
+}
+
+impl Runner for Spawn {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let mut command = Vec::new();
+        if !self.env.is_empty() || self.cwd.is_some() {
+            command.push("env".into());
+            if let Some(cwd) = self.cwd {
+                command.push("-C".into());
+                command.push(cwd.to_string_lossy().into_owned());
+            }
+            command.push("--".into());
+            command.extend(self.env);
+        }
+        command.extend(self.command);
+        niri.send(Request::Action(Action::Spawn { command }));
+    }
+}
+
+impl Runner for SendAction {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let action = match parse_action(&self.action, &self.fields) {
+            Ok(action) => action,
+            Err(e) => exit::fail(Code::Usage, format!("Invalid action: {e}")),
+        };
+        match niri.request(Request::Action(action)) {
+            Ok(Ok(_)) => (),
+            Ok(Err(e)) => {
+                exit::fail(Code::Rejected, format!("Niri rejected action: {e}"))
+            }
+            Err(e) => exit::fail(
+                Code::of(&e).unwrap_or(Code::Protocol),
+                format!("Failed to send action: {e}"),
+            ),
+        }
+    }
+}
+
+impl Runner for SendRequest {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let request = match parse_request(&self.request, &self.fields) {
+            Ok(request) => request,
+            Err(e) => exit::fail(Code::Usage, format!("Invalid request: {e}")),
+        };
+        let reply = query::send(&niri, request);
+        query::print_reply(&reply, "request", format, &config);
+    }
+}
+
+impl Runner for Batch {
+    fn run(self, niri: Niri, _format: Format, config: Config) {
+        let mut failed = false;
+        for (number, line) in io::stdin().lines().enumerate() {
+            let line = line.unwrap_or_else(|e| {
+                exit::fail(Code::Failure, format!("Failed to read stdin: {e}"))
+            });
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let action = batch_action(line).map_err(|e| (Code::Usage, e));
+            let result = action.and_then(|action| {
+                match niri.request(Request::Action(action)) {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err(e)) => Err((
+                        Code::Rejected,
+                        format!("niri rejected action: {e}"),
+                    )),
+                    Err(e) => Err((
+                        Code::of(&e).unwrap_or(Code::Protocol),
+                        format!("failed to send action: {e}"),
+                    )),
+                }
+            });
+            if let Err((code, e)) = result {
+                if !config.quiet() {
+                    exit::report(code, format!("Line {}: {e}", number + 1));
+                }
+                failed = true;
+                if !self.keep_going {
+                    break;
+                }
+            }
+        }
+        if failed {
+            exit::fail(Code::Failure, "Some actions failed");
+        }
+    }
+}
+
+/// The action of batch line
+fn batch_action(line: &str) -> Result<Action, String> {
+    if line.starts_with('{') {
+        return parse_action(line, &[]);
+    }
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    parse_action(name, &words.map(String::from).collect::<Vec<_>>())
+}
+
+/// The action from JSON or from name with `key=value` fields
+fn parse_action(action: &str, fields: &[String]) -> Result<Action, String> {
+    if action.trim_start().starts_with('{') && fields.is_empty() {
+        return serde_json::from_str(action).map_err(|e| e.to_string());
+    }
+    let action =
+        serde_json::json!({ pascal_case(action): fields_object(fields)? });
+    serde_json::from_value(action).map_err(|e| e.to_string())
+}
+
+/// The request from JSON or from name with `key=value` fields
+fn parse_request(request: &str, fields: &[String]) -> Result<Request, String> {
+    let request = request.trim_start();
+    if request.starts_with(['{', '"']) && fields.is_empty() {
+        return serde_json::from_str(request).map_err(|e| e.to_string());
+    }
+    let name = pascal_case(request);
+    let request = if fields.is_empty() {
+        serde_json::Value::String(name)
+    } else {
+        serde_json::json!({ name: fields_object(fields)? })
+    };
+    serde_json::from_value(request).map_err(|e| e.to_string())
+}
+
+/// The JSON object of `key=value` fields
+///
+/// The values are JSON or, when they are not valid JSON, strings.
+fn fields_object(
+    fields: &[String],
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let mut object = serde_json::Map::new();
+    for field in fields {
+        let Some((key, value)) = field.split_once('=') else {
+            return Err(format!("expected KEY=VALUE, got {field:?}"));
+        };
+        let value = serde_json::from_str(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.into()));
+        object.insert(key.replace('-', "_"), value);
+    }
+    Ok(object)
+}
+
+/// The PascalCase name of niri type from kebab-case one, like in niri config
+fn pascal_case(name: &str) -> String {
+    name.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| {
+                    first.to_uppercase().chain(chars).collect::<String>()
+                })
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+impl Runner for MoveWindowToOutput {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let workspaces = workspaces(&niri);
+        let window = windows(&niri).into_iter().find(|window| match self.id {
+            Some(id) => window.id == id,
+            None => window.is_focused,
+        });
+        let Some(window) = window else {
+            exit::fail(Code::Failure, "No such window");
+        };
+        let current = window
+            .workspace_id
+            .and_then(|id| workspaces.iter().find(|ws| ws.id == id))
+            .and_then(|ws| ws.output.clone());
+        let output = self.target.resolve(&niri, current.as_deref());
+        let target = active(&workspaces, &output);
+        niri.send(Request::Action(Action::MoveWindowToWorkspace {
+            window_id: Some(window.id),
+            reference: WorkspaceReferenceArg::Id(target.id),
+        }));
+    }
+}
+
+impl Runner for FocusOutput {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let workspaces = workspaces(&niri);
+        let current = workspaces
+            .iter()
+            .find(|ws| ws.is_focused)
+            .and_then(|ws| ws.output.clone());
+        let output = self.target.resolve(&niri, current.as_deref());
+        let target = active(&workspaces, &output);
+        niri.send(Request::Action(Action::FocusWorkspace {
+            reference: WorkspaceReferenceArg::Id(target.id),
+        }));
+    }
+}
+
+impl Runner for MoveWorkspaceToOutput {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let Some(focused) =
+            workspaces(&niri).into_iter().find(|ws| ws.is_focused)
+        else {
+            exit::fail(Code::Failure, "No focused workspace");
+        };
+        let current = focused.output.as_deref();
+        let output = self.target.resolve(&niri, current);
+        if current == Some(output.as_str()) {
+            return;
+        }
+        niri.send_action(ipc::Action::MoveWorkspaceToMonitor {
+            output,
+            reference: Some(WorkspaceReferenceArg::Id(focused.id)),
+        });
+        // Niri moves the focus along with workspace
+        let reference = match current {
+            Some(current) if !self.follow => {
+                active(&workspaces(&niri), current).id
+            }
+            _ => focused.id,
+        };
+        niri.send(Request::Action(Action::FocusWorkspace {
+            reference: WorkspaceReferenceArg::Id(reference),
+        }));
+    }
+}
+
+impl OutputTarget {
+    /// The name of target output relative to the `current` one
+    fn resolve(self, niri: &Niri, current: Option<&str>) -> String {
+        match self {
+            Self::Name(name) => name,
+            Self::Next => cycle(niri, current, 1),
+            Self::Prev => cycle(niri, current, -1),
+        }
+    }
+}
+
+/// The active workspace of `output`, exits if there is none
+fn active<'a>(workspaces: &'a [Workspace], output: &str) -> &'a Workspace {
+    let active = workspaces
+        .iter()
+        .find(|ws| ws.is_active && ws.output.as_deref() == Some(output));
+    active.unwrap_or_else(|| {
+        exit::fail(
+            Code::Failure,
+            format!("No active workspace on output {output:?}"),
+        )
+    })
+}
+
+/// The name of output `step` positions away from `current` with wraparound
+fn cycle(niri: &Niri, current: Option<&str>, step: isize) -> String {
+    let Response::Outputs(outputs) = niri.send(Request::Outputs) else {
+        exit::fail(Code::Protocol, "Unexpected response to outputs request");
+    };
+    let mut outputs = outputs
+        .into_values()
+        .filter_map(|output| {
+            Some(((output.logical?.x, output.logical?.y), output.name))
+        })
+        .collect::<Vec<_>>();
+    outputs.sort();
+    if outputs.is_empty() {
+        exit::fail(Code::Failure, "No enabled outputs");
+    }
+    let count = outputs.len() as isize;
+    let pos = outputs
+        .iter()
+        .position(|(_, name)| Some(name.as_str()) == current)
+        .map_or(0, |pos| pos as isize + step);
+    outputs.swap_remove(pos.rem_euclid(count) as usize).1
+}
+
+impl Runner for Column {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let (action, window) = match self.action {
+            ColumnAction::Consume(window) => {
+                (Action::ConsumeWindowIntoColumn {}, window)
+            }
+            ColumnAction::Expel(window) => {
+                (Action::ExpelWindowFromColumn {}, window)
+            }
+        };
+        let Some(id) = window.window_id else {
+            niri.send(Request::Action(action));
+            return;
+        };
+        let focused = windows(&niri).into_iter().find(|w| w.is_focused);
+        niri.send(Request::Action(Action::FocusWindow { id }));
+        niri.send(Request::Action(action));
+        if let Some(focused) = focused.filter(|w| w.id != id) {
+            let id = focused.id;
+            niri.send(Request::Action(Action::FocusWindow { id }));
+        }
+    }
+}
+
+impl Runner for ToggleTabbed {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        niri.send_action(ipc::Action::ToggleColumnTabbedDisplay {});
+    }
+}
+
+impl Runner for Resize {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let period = Duration::from_secs(1) / self.rate;
+        let mut next = Instant::now();
+        loop {
+            if let Some(change) = self.width {
+                niri.send(Request::Action(Action::SetColumnWidth { change }));
+            }
+            if let Some(change) = self.height {
+                let action = Action::SetWindowHeight { id: None, change };
+                niri.send(Request::Action(action));
+            }
+            next += period;
+            thread::sleep(next.saturating_duration_since(Instant::now()));
+        }
+    }
+}
+
+impl Runner for ColumnWidth {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let action = match self.width.clone() {
+            WidthChange::Preset => Action::SwitchPresetColumnWidth {},
+            WidthChange::Change(change) => Action::SetColumnWidth { change },
+        };
+        niri.send(Request::Action(action));
+        if config.quiet() {
+            return;
+        }
+        let text = self.width.text();
+        let mut out = Vec::new();
+        match format {
+            Format::Waybar
+            | Format::Ironbar
+            | Format::I3blocks
+            | Format::I3blocksJson => {
+                let message = waybar::Message {
+                    text,
+                    class: vec!["column-width".into()],
+                    ..Default::default()
+                };
+                message.write_as(&format, &mut out)
+            }
+            Format::Json | Format::Jsonl => {
+                let width = serde_json::json!({ "width": text });
+                serde_json::to_writer(&mut out, &width).unwrap()
+            }
+            Format::Yambar => yambar::string(&mut out, "width", &text),
+            Format::Xmobar | Format::Lemonbar | Format::Status => {
+                out.extend_from_slice(text.as_bytes())
+            }
+            Format::Csv => {
+                unreachable!("CSV format is rejected for this command")
+            }
+        }
+        if format == Format::Jsonl {
+            jsonl::wrap("column-width", &mut out);
+        }
+        out.push(b'\n');
+        exit::write_stdout(&out);
+    }
+}
+
+/// The file `name` keeping state between runs in
+/// `$XDG_RUNTIME_DIR/niri-glue`
+fn state_path(name: &str) -> Option<PathBuf> {
+    let dir = PathBuf::from(env::var_os("XDG_RUNTIME_DIR")?);
+    Some(dir.join("niri-glue").join(name))
+}
+
+/// Store state, the failure only makes the next run forget it
+fn write_state(path: &Path, state: &[u8]) {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, state);
+}
+
+/// Ask user in terminal and return whether the answer is yes
+fn confirm(question: &str) -> bool {
+    eprint!("{question} [y/N] ");
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Close all windows and wait for them to disappear
+///
+/// Exits the process if some windows are still open after `timeout`. In
+/// dry-run mode nothing is waited for.
+fn close_all(niri: &Niri, timeout: Duration) {
+    for window in windows(niri) {
+        let id = Some(window.id);
+        niri.send(Request::Action(Action::CloseWindow { id }));
+    }
+    if niri.dry_run() {
+        return;
+    }
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = windows(niri);
+        if left.is_empty() {
+            return;
+        }
+        if Instant::now() >= deadline {
+            exit::fail(
+                Code::Failure,
+                format!("{} windows are still open, not quitting", left.len()),
+            );
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn workspaces(niri: &Niri) -> Vec<Workspace> {
+    match niri.send(Request::Workspaces) {
+        Response::Workspaces(workspaces) => workspaces,
+        _ => exit::fail(
+            Code::Protocol,
+            "Unexpected response to workspaces request",
+        ),
+    }
+}
+
+fn windows(niri: &Niri) -> Vec<Window> {
+    match niri.send(Request::Windows) {
+        Response::Windows(windows) => windows,
+        _ => {
+            exit::fail(Code::Protocol, "Unexpected response to windows request")
+        }
+    }
+}
+
+/// The id of window `id` or of the focused one if it has `app_id`
+///
+/// Exits the process if there is no such window.
+fn guard(niri: &Niri, id: Option<u64>, app_id: &str) -> u64 {
+    let window = windows(niri).into_iter().find(|window| match id {
+        Some(id) => window.id == id,
+        None => window.is_focused,
+    });
+    match window {
+        Some(window) if window.app_id.as_deref() == Some(app_id) => window.id,
+        Some(window) => {
+            let actual = window.app_id.unwrap_or_default();
+            exit::fail(
+                Code::Failure,
+                format!(
+                    "Window {} has app id {actual:?}, not {app_id:?}",
+                    window.id
+                ),
+            );
+        }
+        None => {
+            exit::fail(Code::Failure, "No such window");
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! The display names of applications.
+
+use crate::Config;
+
+use std::collections::HashMap;
+
+/// The built-in display names of widespread applications by their ids
+const NAMES: &[(&str, &str)] = &[
+    ("alacritty", "Alacritty"),
+    ("blender", "Blender"),
+    ("chromium", "Chromium"),
+    ("chromium-browser", "Chromium"),
+    ("code", "VS Code"),
+    ("code-oss", "VS Code"),
+    ("com.mitchellh.ghostty", "Ghostty"),
+    ("com.obsproject.studio", "OBS Studio"),
+    ("discord", "Discord"),
+    ("firefox", "Firefox"),
+    ("foot", "Foot"),
+    ("footclient", "Foot"),
+    ("gimp", "GIMP"),
+    ("google-chrome", "Chrome"),
+    ("kitty", "Kitty"),
+    ("libreoffice-calc", "LibreOffice Calc"),
+    ("libreoffice-writer", "LibreOffice Writer"),
+    ("mpv", "mpv"),
+    ("org.gnome.nautilus", "Files"),
+    ("org.gnome.ptyxis", "Terminal"),
+    ("org.gnome.texteditor", "Text Editor"),
+    ("org.inkscape.inkscape", "Inkscape"),
+    ("org.keepassxc.keepassxc", "KeePassXC"),
+    ("org.kde.dolphin", "Dolphin"),
+    ("org.kde.konsole", "Konsole"),
+    ("org.kde.okular", "Okular"),
+    ("org.mozilla.firefox", "Firefox"),
+    ("org.mozilla.thunderbird", "Thunderbird"),
+    ("org.pwmt.zathura", "Zathura"),
+    ("org.qutebrowser.qutebrowser", "qutebrowser"),
+    ("org.telegram.desktop", "Telegram"),
+    ("org.wezfurlong.wezterm", "WezTerm"),
+    ("pavucontrol", "Volume Control"),
+    ("signal", "Signal"),
+    ("slack", "Slack"),
+    ("spotify", "Spotify"),
+    ("steam", "Steam"),
+    ("thunar", "Thunar"),
+    ("thunderbird", "Thunderbird"),
+    ("vlc", "VLC"),
+];
+
+/// Resolver of display names of applications by app_id
+///
+/// The names from configuration take precedence over the built-in ones. The
+/// ids are matched ignoring case, the unknown ones are shown as is.
+#[derive(Debug, Clone, Default)]
+pub struct AppNames {
+    names: HashMap<String, String>,
+}
+
+impl AppNames {
+    /// The built-in names extended with ones from `config` and `extra`
+    pub fn new<'a>(
+        config: &Config,
+        extra: impl IntoIterator<Item = &'a (String, String)>,
+    ) -> Self {
+        let builtin = NAMES
+            .iter()
+            .map(|(id, name)| (id.to_string(), name.to_string()));
+        let names = builtin
+            .chain(config.app_names.clone())
+            .chain(extra.into_iter().cloned())
+            .map(|(id, name)| (id.to_lowercase(), name))
+            .collect();
+        Self { names }
+    }
+
+    /// The display name of application with given app_id
+    pub fn name(&self, app_id: &str) -> String {
+        self.names
+            .get(&app_id.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| app_id.to_owned())
+    }
+}
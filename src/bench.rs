@@ -0,0 +1,92 @@
+use crate::{
+    monitor::Monitor, testing, Config, FocusedWindow, FocusedWorkspace, Format,
+    Group, Layout, Multi, Niri, Pager, Runner, Status, Windows, Workspaces,
+};
+
+use clap::{Parser, Subcommand};
+use std::time::Instant;
+
+/// Event throughput benchmark.
+///
+/// Pushes synthetic event stream through the chosen monitor without
+/// connecting to niri and reports the rate of processed events.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Bench {
+    /// Count of events to push
+    #[arg(short, long, default_value_t = 100_000)]
+    events: usize,
+
+    /// Count of windows in synthetic state
+    #[arg(short, long, default_value_t = 50)]
+    windows: u64,
+
+    /// The monitor to benchmark with its options
+    #[command(subcommand)]
+    target: Target,
+}
+
+/// The monitors to benchmark
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum Target {
+    Layout(Layout),
+    Pager(Pager),
+    Workspaces(Workspaces),
+    Workspace(FocusedWorkspace),
+    Windows(Windows),
+    Window(FocusedWindow),
+    Status(Status),
+    Group(Group),
+    Multi(Multi),
+}
+
+impl Runner for Bench {
+    fn run(self, _niri: Niri, format: Format, config: Config) {
+        let monitor = self.target.monitor(format, &config);
+        measure(monitor, self.events, self.windows)
+    }
+}
+
+impl Target {
+    /// The monitor configured by these options
+    pub(crate) fn monitor(
+        self,
+        format: Format,
+        config: &Config,
+    ) -> Box<dyn Monitor> {
+        match self {
+            Self::Layout(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Pager(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Workspaces(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Workspace(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Windows(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Window(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Status(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Group(cmd) => Box::new(cmd.monitor(format, config)),
+            Self::Multi(cmd) => Box::new(cmd.monitor(format, config)),
+        }
+    }
+}
+
+/// Apply `count` events to `monitor` rendering after each one
+fn measure(mut monitor: impl Monitor, count: usize, windows: u64) {
+    let mut out = Vec::new();
+    let mut bytes = 0;
+    for event in testing::state(windows) {
+        monitor.apply(event);
+    }
+    let start = Instant::now();
+    for i in 0..count {
+        if monitor.apply(testing::event(i, windows)) {
+            out.clear();
+            monitor.render(&mut out);
+            bytes += out.len();
+        }
+    }
+    let elapsed = start.elapsed();
+    let rate = count as f64 / elapsed.as_secs_f64();
+    println!(
+        "{count} events in {:.3}s, {rate:.0} events/s, {bytes} bytes rendered",
+        elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,60 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The file keeping the last line printed by monitor
+///
+/// The file is chosen by command line arguments, so each monitor instance
+/// with its own options has its own cache file in
+/// `$XDG_CACHE_HOME/niri-glue`.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    path: PathBuf,
+}
+
+impl Cache {
+    /// The cache of current process, if cache directory is known
+    pub fn new() -> Option<Self> {
+        // Arguments can not contain NUL, so it separates them unambiguously
+        let args: Vec<_> = env::args().skip(1).collect();
+        let name = format!("{:016x}", fnv(args.join("\0").as_bytes()));
+        Some(Self {
+            path: cache_dir()?.join(name),
+        })
+    }
+
+    /// The line stored by previous run
+    pub fn read(&self) -> Option<Vec<u8>> {
+        let line = fs::read(&self.path).ok()?;
+        Some(line).filter(|line| !line.is_empty())
+    }
+
+    /// Store line for the next run
+    pub fn write(&self, line: &[u8]) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write and rename, so concurrent readers never see partial line
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, line)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// FNV-1a hash of `bytes`, which does not depend on the version of Rust
+pub fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    let dir =
+        env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|h| Path::new(&h).join(".cache"))
+            })?;
+    Some(dir.join("niri-glue"))
+}
@@ -0,0 +1,125 @@
+//! The description of commands and formats for wrapper tools.
+
+use crate::{exit, Format};
+#[cfg(feature = "monitors")]
+use crate::{Group, Multi};
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+/// The part of description to print
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Listing {
+    /// The output formats
+    Formats,
+    /// The commands
+    Commands,
+    /// The commands with formats each of them supports
+    Capabilities,
+}
+
+/// Print `listing` of `command` as text lines or as JSON
+pub fn print(command: &clap::Command, listing: Listing, json: bool) {
+    let formats = formats();
+    let commands: Vec<_> = command
+        .get_subcommands()
+        .filter(|cmd| !cmd.is_hide_set())
+        .collect();
+    if json {
+        let value = match listing {
+            Listing::Formats => {
+                Value::Array(formats.iter().map(describe).collect())
+            }
+            Listing::Commands => commands
+                .iter()
+                .map(|cmd| json!({"name": cmd.get_name(), "about": about(cmd)}))
+                .collect(),
+            Listing::Capabilities => json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "features": features(),
+                "formats": formats.iter().map(describe).collect::<Vec<_>>(),
+                "commands": commands
+                    .iter()
+                    .map(|cmd| json!({
+                        "name": cmd.get_name(),
+                        "about": about(cmd),
+                        "formats": supported(cmd.get_name(), &formats),
+                    }))
+                    .collect::<Vec<_>>(),
+            }),
+        };
+        return exit::write_stdout(format!("{value}\n").as_bytes());
+    }
+    let lines: Vec<_> = match listing {
+        Listing::Formats => formats.iter().map(name).collect(),
+        Listing::Commands => commands
+            .iter()
+            .map(|cmd| cmd.get_name().to_owned())
+            .collect(),
+        Listing::Capabilities => commands
+            .iter()
+            .map(|cmd| {
+                let name = cmd.get_name();
+                format!("{name}: {}", supported(name, &formats).join(" "))
+            })
+            .collect(),
+    };
+    exit::write_stdout(format!("{}\n", lines.join("\n")).as_bytes());
+}
+
+/// Whether built-in `command` may output messages in `format`
+///
+/// The commands check their formats with the same rules. The commands of
+/// other crates are expected to support all formats.
+pub fn supports(command: &str, format: &Format) -> bool {
+    match (command, format) {
+        ("query" | "request", _) => true,
+        (_, Format::Csv) => false,
+        #[cfg(feature = "monitors")]
+        ("group", format) => Group::supports(format),
+        #[cfg(feature = "monitors")]
+        ("multi", format) => Multi::supports(format),
+        _ => true,
+    }
+}
+
+fn formats() -> Vec<Format> {
+    Format::value_variants().to_vec()
+}
+
+/// The name of `format` on command line, like `i3blocks-json`
+pub fn name(format: &Format) -> String {
+    let value = format.to_possible_value().unwrap();
+    value.get_name().to_owned()
+}
+
+fn describe(format: &Format) -> Value {
+    let value = format.to_possible_value().unwrap();
+    let help = value.get_help().map(ToString::to_string);
+    json!({"name": value.get_name(), "description": help})
+}
+
+fn about(command: &clap::Command) -> Option<String> {
+    command.get_about().map(ToString::to_string)
+}
+
+fn supported(command: &str, formats: &[Format]) -> Vec<String> {
+    formats
+        .iter()
+        .filter(|format| supports(command, format))
+        .map(name)
+        .collect()
+}
+
+/// The enabled cargo features
+fn features() -> Vec<&'static str> {
+    [
+        ("monitors", cfg!(feature = "monitors")),
+        ("actions", cfg!(feature = "actions")),
+        ("tui", cfg!(feature = "tui")),
+        ("rewrite", cfg!(feature = "rewrite")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
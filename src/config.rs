@@ -0,0 +1,539 @@
+use crate::{
+    exit::{self, Code},
+    waybar::{Markup, Syntax},
+    Format,
+};
+
+use clap::Parser;
+#[cfg(feature = "rewrite")]
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Error},
+    path::{Path, PathBuf},
+};
+
+/// The maximal depth of nested includes
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// The types of niri events monitors may be subscribed to
+pub const EVENTS: [&str; 9] = [
+    "WorkspacesChanged",
+    "WorkspaceActivated",
+    "WorkspaceActiveWindowChanged",
+    "WindowsChanged",
+    "WindowOpenedOrChanged",
+    "WindowClosed",
+    "WindowFocusChanged",
+    "KeyboardLayoutsChanged",
+    "KeyboardLayoutSwitched",
+];
+
+/// The contents of configuration file
+///
+/// The configuration file is JSON document. It is looked up in
+/// `$XDG_CONFIG_HOME/niri-glue/config.json` unless the path is specified
+/// explicitly. Missing default file is treated as empty configuration.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Whether to escape markup in emitted texts
+    ///
+    /// By default texts are escaped for waybar, ironbar, xmobar and lemonbar
+    /// formats only.
+    pub escape_markup: Option<bool>,
+
+    /// The format of output messages, see `--format`
+    pub format: Option<Format>,
+
+    /// Interval in seconds to re-emit the current state of monitors
+    pub heartbeat: Option<u64>,
+
+    /// Interval in milliseconds to poll niri if event stream is unavailable
+    pub poll_interval: Option<u64>,
+
+    /// Delay in milliseconds to wait for event storms to settle down before
+    /// rendering
+    pub debounce: Option<u64>,
+
+    /// Time in milliseconds to gather the initial state of monitors before
+    /// the first render, 50 by default
+    pub settle: Option<u64>,
+
+    /// Count of lines to write before flushing stdout, 1 by default
+    pub flush_every: Option<u64>,
+
+    /// Shell command formatting lines of monitors, see `--formatter-cmd`
+    pub formatter_cmd: Option<String>,
+
+    /// Path to native formatter plugin, see `--plugin`
+    pub plugin: Option<PathBuf>,
+
+    /// Whether to suppress success output and warnings of actions
+    pub quiet: Option<bool>,
+
+    /// The types of niri events processed by monitors, see `--event`
+    pub events: Option<Vec<String>>,
+
+    /// Aliases of keyboard layouts, see `layout --alias`
+    pub aliases: HashMap<String, String>,
+
+    /// Display names of applications by their ids, see `windows --app-name`
+    pub app_names: HashMap<String, String>,
+
+    /// Glyphs of workspaces, see `workspaces --icon`
+    pub workspace_icons: WorkspaceIcons,
+
+    /// Window title rewrite rules
+    #[cfg(feature = "rewrite")]
+    pub rewrite: Vec<Rewrite>,
+
+    /// The files to take options from, relative to the including one
+    ///
+    /// The options of including file take precedence over included ones,
+    /// and the options of later included files over earlier ones.
+    pub include: Vec<PathBuf>,
+
+    /// The sets of options applied on hosts with given names
+    ///
+    /// The options for current host take precedence over the rest of
+    /// configuration but not over the profile.
+    pub hosts: HashMap<String, Config>,
+
+    /// The named sets of options chosen with `--profile`
+    ///
+    /// The options of chosen profile take precedence over the rest of
+    /// configuration.
+    pub profiles: HashMap<String, Config>,
+
+    /// The source the configuration was loaded from
+    #[serde(skip)]
+    origin: Origin,
+}
+
+/// The path, profile and command line overrides configuration was built from
+#[derive(Debug, Clone, Default)]
+struct Origin {
+    path: Option<PathBuf>,
+    profile: Option<String>,
+    overrides: Option<Box<Config>>,
+}
+
+/// The glyphs of workspaces by their names or indices
+///
+/// The glyphs for the state of workspace take precedence over ones for any
+/// state.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceIcons {
+    /// The glyphs of workspaces in any state
+    pub all: HashMap<String, String>,
+    /// The glyphs of the focused workspace
+    pub focused: HashMap<String, String>,
+    /// The glyphs of workspaces with windows
+    pub occupied: HashMap<String, String>,
+    /// The glyphs of workspaces without windows
+    pub empty: HashMap<String, String>,
+}
+
+impl WorkspaceIcons {
+    /// Take the glyphs of `other` in addition to these ones
+    pub fn merge(&mut self, other: &WorkspaceIcons) {
+        self.all.extend(other.all.clone());
+        self.focused.extend(other.focused.clone());
+        self.occupied.extend(other.occupied.clone());
+        self.empty.extend(other.empty.clone());
+    }
+}
+
+/// The rule of window title rewriting
+#[cfg(feature = "rewrite")]
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Rewrite {
+    /// Regex of application ids the rule applies to, all if unset
+    #[serde(default)]
+    pub app_id: Option<String>,
+    /// Regex to search in title
+    pub pattern: String,
+    /// Replacement of pattern matches, may refer groups as `$1` or `$name`
+    #[serde(default)]
+    pub replacement: String,
+}
+
+impl Config {
+    /// Load configuration from `path` or from default location
+    ///
+    /// The options of `profile` if given and then the options set in
+    /// `overrides` take precedence over ones from file.
+    pub fn load(
+        path: Option<&Path>,
+        profile: Option<&str>,
+        overrides: Config,
+    ) -> Self {
+        Self::try_load(path, profile, overrides)
+            .unwrap_or_else(|e| exit::fail(Code::Usage, e))
+    }
+
+    /// Load configuration again from the same source
+    pub fn reload(&self) -> io::Result<Self> {
+        let overrides = self.origin.overrides.as_deref().cloned();
+        Self::try_load(
+            self.origin.path.as_deref(),
+            self.origin.profile.as_deref(),
+            overrides.unwrap_or_default(),
+        )
+    }
+
+    /// The path of configuration file given by option or environment
+    #[cfg(feature = "actions")]
+    pub fn given_path(&self) -> Option<&Path> {
+        self.origin.path.as_deref()
+    }
+
+    /// The path of configuration file, which may not exist
+    #[cfg(feature = "monitors")]
+    pub fn path(&self) -> Option<PathBuf> {
+        self.origin.path.clone().or_else(default_path)
+    }
+
+    /// The configuration file along with files it includes, directly or
+    /// nested
+    ///
+    /// The includes are listed even if they are missing or broken, so they
+    /// may be watched until fixed.
+    #[cfg(feature = "monitors")]
+    pub fn sources(&self) -> Vec<PathBuf> {
+        let mut sources = Vec::new();
+        if let Some(path) = self.path() {
+            includes(&path, 0, &mut sources);
+        }
+        sources
+    }
+
+    fn try_load(
+        path: Option<&Path>,
+        profile: Option<&str>,
+        overrides: Config,
+    ) -> io::Result<Self> {
+        let mut config = Self::read(path)?;
+        if let Some(host) = hostname().and_then(|h| config.hosts.remove(&h)) {
+            config.merge(&host);
+        }
+        if let Some(name) = profile {
+            let profile = config.profiles.remove(name).ok_or_else(|| {
+                Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Unknown profile `{name}`"),
+                )
+            })?;
+            config.merge(&profile);
+        }
+        config.check()?;
+        config.merge(&overrides);
+        config.origin = Origin {
+            path: path.map(Path::to_owned),
+            profile: profile.map(str::to_owned),
+            overrides: Some(Box::new(overrides)),
+        };
+        Ok(config)
+    }
+
+    /// Check the options which are not checked by parsing
+    fn check(&self) -> io::Result<()> {
+        if self.aliases.contains_key("") {
+            return Err(Error::new(
+                io::ErrorKind::InvalidData,
+                "Empty source of alias would match any layout",
+            ));
+        }
+        let mut events = self.events.iter().flatten();
+        if let Some(event) = events.find(|e| !EVENTS.contains(&e.as_str())) {
+            return Err(Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown event type `{event}`"),
+            ));
+        }
+        #[cfg(feature = "rewrite")]
+        for rewrite in &self.rewrite {
+            rewrite.compile().map_err(|e| {
+                Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid rewrite rule `{}`: {e}", rewrite.pattern),
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    fn read(path: Option<&Path>) -> io::Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_owned(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Default::default()),
+            },
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&path, &content, 0),
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => {
+                Ok(Default::default())
+            }
+            Err(e) => Err(Error::new(
+                e.kind(),
+                format!("Failed to read {}: {e}", path.display()),
+            )),
+        }
+    }
+
+    /// Parse `content` of file at `path` and resolve its includes
+    fn parse(path: &Path, content: &str, depth: usize) -> io::Result<Self> {
+        let config: Self = serde_json::from_str(content).map_err(|e| {
+            Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse {}: {e}", path.display()),
+            )
+        })?;
+        if config.include.is_empty() {
+            return Ok(config);
+        }
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Too deep includes in {}", path.display()),
+            ));
+        }
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut merged = Self::default();
+        for include in &config.include {
+            let path = dir.join(include);
+            let content = fs::read_to_string(&path).map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    format!("Failed to read {}: {e}", path.display()),
+                )
+            })?;
+            merged.merge(&Self::parse(&path, &content, depth + 1)?);
+        }
+        merged.merge(&config);
+        Ok(merged)
+    }
+
+    /// Take the options set in `other`
+    fn merge(&mut self, other: &Config) {
+        if other.escape_markup.is_some() {
+            self.escape_markup = other.escape_markup;
+        }
+        if other.format.is_some() {
+            self.format.clone_from(&other.format);
+        }
+        if other.heartbeat.is_some() {
+            self.heartbeat = other.heartbeat;
+        }
+        if other.poll_interval.is_some() {
+            self.poll_interval = other.poll_interval;
+        }
+        if other.debounce.is_some() {
+            self.debounce = other.debounce;
+        }
+        if other.settle.is_some() {
+            self.settle = other.settle;
+        }
+        if other.flush_every.is_some() {
+            self.flush_every = other.flush_every;
+        }
+        if other.formatter_cmd.is_some() {
+            self.formatter_cmd.clone_from(&other.formatter_cmd);
+        }
+        if other.quiet.is_some() {
+            self.quiet = other.quiet;
+        }
+        if other.plugin.is_some() {
+            self.plugin.clone_from(&other.plugin);
+        }
+        if other.events.is_some() {
+            self.events.clone_from(&other.events);
+        }
+        self.aliases.extend(other.aliases.clone());
+        self.app_names.extend(other.app_names.clone());
+        self.workspace_icons.merge(&other.workspace_icons);
+        #[cfg(feature = "rewrite")]
+        self.rewrite.extend(other.rewrite.iter().cloned());
+        self.hosts.extend(other.hosts.clone());
+        self.profiles.extend(other.profiles.clone());
+    }
+
+    /// Whether monitors process niri events of type `event`
+    #[cfg(feature = "monitors")]
+    pub fn subscribed(&self, event: &str) -> bool {
+        self.events
+            .as_ref()
+            .is_none_or(|events| events.iter().any(|e| e == event))
+    }
+
+    /// Whether actions should print nothing on success
+    #[cfg(feature = "actions")]
+    pub fn quiet(&self) -> bool {
+        self.quiet.unwrap_or(false)
+    }
+
+    /// The markup escaping policy for texts emitted in `format`
+    pub fn markup(&self, format: &Format) -> Markup {
+        let syntax = match format {
+            Format::Xmobar => Syntax::Xmobar,
+            Format::Lemonbar => Syntax::Lemonbar,
+            Format::Status => Syntax::Plain,
+            _ => Syntax::Pango,
+        };
+        let escape = matches!(
+            format,
+            Format::Waybar
+                | Format::Ironbar
+                | Format::Xmobar
+                | Format::Lemonbar
+        );
+        Markup {
+            escape: self.escape_markup.unwrap_or(escape),
+            syntax,
+        }
+    }
+}
+
+#[cfg(feature = "rewrite")]
+impl Rewrite {
+    /// Parse rule from `APP_ID=PATTERN=REPLACEMENT` string
+    ///
+    /// The empty `APP_ID` matches all applications.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let mut parts = s.splitn(3, '=');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(app_id), Some(pattern), replacement) => Ok(Self {
+                app_id: Some(app_id.to_owned()).filter(|a| !a.is_empty()),
+                pattern: pattern.to_owned(),
+                replacement: replacement.unwrap_or_default().to_owned(),
+            }),
+            _ => Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid APP_ID=PATTERN=REPLACEMENT: `{s}`"),
+            )),
+        }
+    }
+
+    /// Compile regexes of the rule
+    pub fn compile(&self) -> Result<RewriteRule, Error> {
+        let regex = |re: &str| Regex::new(re).map_err(Error::other);
+        Ok(RewriteRule {
+            app_id: self.app_id.as_deref().map(regex).transpose()?,
+            pattern: regex(&self.pattern)?,
+            replacement: self.replacement.clone(),
+        })
+    }
+}
+
+/// The compiled rule of window title rewriting
+#[cfg(feature = "rewrite")]
+#[derive(Debug, Clone)]
+pub struct RewriteRule {
+    app_id: Option<Regex>,
+    pattern: Regex,
+    replacement: String,
+}
+
+#[cfg(feature = "rewrite")]
+impl RewriteRule {
+    /// Rewrite title of window of application `app_id` if rule matches it
+    pub fn apply(&self, app_id: &str, title: &str) -> String {
+        if self.app_id.as_ref().is_some_and(|re| !re.is_match(app_id)) {
+            return title.to_owned();
+        }
+        let title = self.pattern.replace_all(title, &self.replacement);
+        title.into_owned()
+    }
+}
+
+/// Append `path` and paths of files included by it to `sources`
+#[cfg(feature = "monitors")]
+fn includes(path: &Path, depth: usize, sources: &mut Vec<PathBuf>) {
+    /// The only option which matters here, the rest is checked by loading
+    #[derive(Deserialize, Default)]
+    #[serde(default)]
+    struct Includes {
+        include: Vec<PathBuf>,
+    }
+
+    sources.push(path.to_owned());
+    if depth >= MAX_INCLUDE_DEPTH {
+        return;
+    }
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let Ok(parsed) = serde_json::from_str::<Includes>(&content) else {
+        return;
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for include in parsed.include {
+        includes(&dir.join(include), depth + 1, sources);
+    }
+}
+
+/// Configuration checker.
+///
+/// Loads configuration file with its includes and checks it along with each
+/// of its host sections and profiles. The error is reported with line and
+/// column of file where possible.
+#[derive(Parser, Debug, Clone)]
+pub struct ValidateConfig {}
+
+impl ValidateConfig {
+    /// Check configuration at `path` or at default location
+    ///
+    /// Exits with code 2 if configuration is invalid.
+    pub fn run(self, path: Option<&Path>, quiet: bool) {
+        if let Err(e) = Self::validate(path) {
+            exit::fail(Code::Usage, e);
+        }
+        if quiet {
+            return;
+        }
+        let path = path.map(Path::to_owned).or_else(default_path);
+        let report = match path {
+            Some(path) => format!("{} is valid\n", path.display()),
+            None => "Configuration is valid\n".to_owned(),
+        };
+        exit::write_stdout(report.as_bytes());
+    }
+
+    fn validate(path: Option<&Path>) -> io::Result<()> {
+        let config = Config::read(path)?;
+        config.check()?;
+        let sections = config.hosts.iter().map(|(name, c)| ("host", name, c));
+        let profiles = config.profiles.iter().map(|(n, c)| ("profile", n, c));
+        for (kind, name, section) in sections.chain(profiles) {
+            let mut merged = config.clone();
+            merged.merge(section);
+            merged.check().map_err(|e| {
+                Error::new(e.kind(), format!("In {kind} `{name}`: {e}"))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// The name of current host
+fn hostname() -> Option<String> {
+    let name = fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .ok()?;
+    Some(name.trim().to_owned()).filter(|name| !name.is_empty())
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dir =
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|h| Path::new(&h).join(".config"))
+            })?;
+    Some(dir.join("niri-glue").join("config.json"))
+}
@@ -0,0 +1,113 @@
+//! The exit codes of process and reporting of failures.
+//!
+//! The scripts and supervisors may tell the cause of failure by code:
+//!
+//! - `1` – other failure, like missing window or workspace;
+//! - `2` – invalid arguments or configuration;
+//! - `3` – niri is unreachable;
+//! - `4` – niri replied with something unexpected or closed connection;
+//! - `5` – niri rejected action or request;
+//! - `6` – stdout is closed or not writable;
+//! - `7` – niri did not reply in time set by `--timeout`;
+//! - `101` – bug of niri-glue.
+//!
+//! The failure is reported to stderr as text or with `--errors=json` as
+//! JSON object like `{"error":"unreachable","code":3,"message":"..."}`.
+//!
+//! The [modules](crate::Module) may fail with the same codes.
+
+use clap::ValueEnum;
+use serde_json::json;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether failures are reported as JSON
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// The cause of failure
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Code {
+    /// Other failure
+    Failure = 1,
+    /// Invalid arguments or configuration
+    Usage = 2,
+    /// Niri socket can not be connected
+    Unreachable = 3,
+    /// Unexpected reply or failure of connection
+    Protocol = 4,
+    /// The action or request rejected by niri
+    Rejected = 5,
+    /// The output is not writable
+    BrokenPipe = 6,
+    /// Niri did not reply in time
+    Timeout = 7,
+}
+
+impl Code {
+    /// The name of cause in JSON reports
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Failure => "failure",
+            Self::Usage => "usage",
+            Self::Unreachable => "unreachable",
+            Self::Protocol => "protocol",
+            Self::Rejected => "rejected",
+            Self::BrokenPipe => "broken-pipe",
+            Self::Timeout => "timeout",
+        }
+    }
+
+    /// The code of socket `error` if it has its own one
+    pub fn of(error: &io::Error) -> Option<Self> {
+        match error.kind() {
+            io::ErrorKind::TimedOut => Some(Self::Timeout),
+            _ => None,
+        }
+    }
+}
+
+/// The form of failure reports
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Errors {
+    /// The text line for humans
+    Human,
+    /// The JSON object per line
+    Json,
+}
+
+/// Report the following failures in form of `errors`
+pub fn report_as(errors: Errors) {
+    JSON.store(errors == Errors::Json, Ordering::Relaxed);
+}
+
+/// Report `message` to stderr and exit with `code`
+pub fn fail(code: Code, message: impl Display) -> ! {
+    report(code, message);
+    process::exit(code as i32)
+}
+
+/// Report `message` of failure with `code` to stderr without exiting
+pub fn report(code: Code, message: impl Display) {
+    if JSON.load(Ordering::Relaxed) {
+        let report = json!({
+            "error": code.name(),
+            "code": code as i32,
+            "message": message.to_string(),
+        });
+        eprintln!("{report}");
+    } else {
+        eprintln!("{message}");
+    }
+}
+
+/// Write `out` to stdout or exit with [Code::BrokenPipe]
+pub fn write_stdout(out: &[u8]) {
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = stdout.write_all(out).and_then(|_| stdout.flush()) {
+        fail(Code::BrokenPipe, format!("Failed to write output: {e}"));
+    }
+}
@@ -0,0 +1,129 @@
+//! The restoring of focus on returns to workspaces.
+
+use crate::{
+    exit::{self, Code},
+    Config, Format, Niri, Runner,
+};
+
+use clap::Parser;
+use niri_ipc::{
+    state::{EventStreamState, EventStreamStatePart},
+    Action, Event, Request,
+};
+use std::collections::HashMap;
+
+/// Focus keeper.
+///
+/// Runs until niri exits, remembers the last focused window of each
+/// workspace and focuses it again when the workspace is activated, in case
+/// the focus lands on another column after switching. The window focused
+/// directly, like by `focus-window` action or taskbar click, is kept.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct RestoreFocus {}
+
+impl Runner for RestoreFocus {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let mut events = match niri.connect().send(Request::EventStream) {
+            Ok((Ok(_), events)) => events,
+            Ok((Err(e), _)) => exit::fail(
+                Code::Rejected,
+                format!("Niri rejected event stream request: {e}"),
+            ),
+            Err(e) => exit::fail(
+                Code::of(&e).unwrap_or(Code::Protocol),
+                format!("Failed to request event stream: {e}"),
+            ),
+        };
+        let mut keeper = Keeper::default();
+        loop {
+            let event = events().unwrap_or_else(|e| {
+                exit::fail(
+                    Code::of(&e).unwrap_or(Code::Protocol),
+                    format!("Failed to read event stream: {e}"),
+                )
+            });
+            if let Some(id) = keeper.apply(event) {
+                niri.send(Request::Action(Action::FocusWindow { id }));
+            }
+        }
+    }
+}
+
+/// The last focused windows of workspaces
+#[derive(Default)]
+struct Keeper {
+    state: EventStreamState,
+    /// The ids of windows by ids of their workspaces
+    focused: HashMap<u64, u64>,
+    /// The activation of workspace waiting for focus to land on it
+    activated: Option<Activation>,
+}
+
+/// The workspace activated by user and the window focused on it directly
+struct Activation {
+    workspace: u64,
+    /// The window which became active on workspace along with activation,
+    /// as on focusing window by id or by click on taskbar
+    targeted: Option<u64>,
+}
+
+impl Keeper {
+    /// Apply `event` and return the window to focus if any
+    fn apply(&mut self, event: Event) -> Option<u64> {
+        // Niri reports the activation first, then the window which got the
+        // active one on that workspace if any, and the focus last
+        let mut landed = None;
+        match &event {
+            Event::WorkspaceActivated { id, focused: true } => {
+                self.activated = Some(Activation {
+                    workspace: *id,
+                    targeted: None,
+                })
+            }
+            Event::WorkspaceActiveWindowChanged {
+                workspace_id,
+                active_window_id,
+            } => {
+                if let Some(activation) = &mut self.activated {
+                    if activation.workspace == *workspace_id {
+                        activation.targeted = *active_window_id;
+                    }
+                }
+            }
+            Event::WindowFocusChanged { id } => landed = Some(*id),
+            _ => (),
+        }
+        self.state.apply(event);
+        let refocus = landed.and_then(|id| self.refocus(id));
+        let windows = &self.state.windows.windows;
+        let current = windows.values().find(|window| window.is_focused);
+        if let Some(window) = current {
+            if let Some(workspace) = window.workspace_id {
+                self.focused.insert(workspace, window.id);
+            }
+        }
+        refocus
+    }
+
+    /// The window to focus instead of `landed` one after activation
+    ///
+    /// The focus is restored only if it landed on another window of the
+    /// activated workspace, which was not targeted by user.
+    fn refocus(&mut self, landed: Option<u64>) -> Option<u64> {
+        let activation = self.activated.take()?;
+        let landed = landed?;
+        let workspace = Some(activation.workspace);
+        let windows = &self.state.windows.windows;
+        let id = *self.focused.get(&activation.workspace)?;
+        if id == landed || activation.targeted == Some(landed) {
+            return None;
+        }
+        let on_workspace = |id| {
+            windows
+                .get(&id)
+                .is_some_and(|w| w.workspace_id == workspace)
+        };
+        (on_workspace(landed) && on_workspace(id)).then_some(id)
+    }
+}
@@ -0,0 +1,87 @@
+//! The user code formatting lines of monitors.
+//!
+//! Each record of monitor state is JSON like
+//! `{"time":"...","type":"WindowFocusChanged","data":{...}}`, where `data` is
+//! the state rendered in chosen format, the text ones being JSON strings.
+//! The single line formatter produces for record is emitted instead.
+
+use crate::plugin::Plugin;
+
+use std::{
+    io::{self, BufRead, BufReader, Error, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+/// The formatter of records of monitor state
+pub enum Formatter {
+    /// The program run by `--formatter-cmd`
+    Program(Program),
+    /// The shared library loaded by `--plugin`
+    Plugin(Plugin),
+}
+
+impl Formatter {
+    /// Replace `record` with the line formatter produced for it
+    pub fn format(&mut self, record: &mut Vec<u8>) -> io::Result<()> {
+        match self {
+            Self::Program(program) => program.format(record),
+            Self::Plugin(plugin) => plugin.format(record),
+        }
+    }
+}
+
+/// The subprocess turning records into lines of output
+///
+/// The command is run by `sh -c` once for the whole life of monitor. Each
+/// record is written to its stdin as single line, and the line it prints in
+/// reply is taken.
+pub struct Program {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    reply: String,
+}
+
+impl Program {
+    /// Spawn formatter `command`
+    pub fn spawn(command: &str) -> io::Result<Self> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+            reply: String::new(),
+        })
+    }
+
+    /// Replace `record` with the line formatter printed for it
+    pub fn format(&mut self, record: &mut Vec<u8>) -> io::Result<()> {
+        record.push(b'\n');
+        self.stdin.write_all(record)?;
+        self.stdin.flush()?;
+        self.reply.clear();
+        if self.stdout.read_line(&mut self.reply)? == 0 {
+            return Err(Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "formatter exited",
+            ));
+        }
+        record.clear();
+        record.extend_from_slice(self.reply.trim_end_matches('\n').as_bytes());
+        Ok(())
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
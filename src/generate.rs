@@ -0,0 +1,257 @@
+//! The snippets of bar configurations running monitors.
+
+use crate::{exit, Config, Format, Niri, Runner};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::{json, Map, Value};
+
+/// Bar configuration generator.
+///
+/// Prints the snippet of bar configuration with modules running chosen
+/// monitors, their clicks and scrolls wired to action commands. The snippet
+/// is meant to be pasted into configuration and adjusted there.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Generate {
+    #[command(subcommand)]
+    target: Target,
+}
+
+/// The bars to generate configuration for
+#[derive(Subcommand, Debug, Clone)]
+enum Target {
+    /// Waybar `custom/niri-*` modules as JSON object
+    Waybar(Widgets),
+    /// Eww `deflisten` variables with example widgets as yuck
+    Eww(Widgets),
+}
+
+/// The options of generated modules
+#[derive(Args, Debug, Clone)]
+struct Widgets {
+    /// The monitors to generate modules for, all by default
+    #[arg(value_enum)]
+    monitors: Vec<Widget>,
+
+    /// Show only workspaces and windows of the given output
+    ///
+    /// The output name is appended to names of modules to tell them apart
+    /// in configuration of bar per output.
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// The command running niri-glue
+    #[arg(long, default_value = "niri-glue")]
+    program: String,
+}
+
+/// The monitors suitable for modules of bars
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Widget {
+    /// Keyboard layout, switched on click
+    Layout,
+    /// Workspace pager, switched on scroll
+    Pager,
+    /// Workspaces buttons, switched on scroll
+    Workspaces,
+    /// Focused workspace, overview on click
+    FocusedWorkspace,
+    /// Windows list
+    Windows,
+    /// Focused window, closed on middle click
+    Window,
+}
+
+impl Widget {
+    /// The name of monitor command
+    fn name(self) -> String {
+        self.to_possible_value().unwrap().get_name().to_owned()
+    }
+
+    /// Whether monitor has JSON format, which is richer than waybar one
+    fn json(self) -> bool {
+        matches!(self, Self::FocusedWorkspace | Self::Windows | Self::Window)
+    }
+
+    /// Whether monitor can be limited to single output
+    fn per_output(self) -> bool {
+        self != Self::Layout
+    }
+
+    /// The action commands of clicks and scrolls with waybar names of events
+    fn handlers(self) -> &'static [(&'static str, &'static str)] {
+        const SCROLL: &[(&str, &str)] = &[
+            ("on-scroll-up", "focus-workspace prev"),
+            ("on-scroll-down", "focus-workspace next"),
+        ];
+        match self {
+            Self::Layout => &[("on-click", "switch-layout")],
+            Self::Pager | Self::Workspaces => SCROLL,
+            Self::FocusedWorkspace => &[
+                ("on-click", "toggle-overview"),
+                ("on-scroll-up", "focus-workspace prev"),
+                ("on-scroll-down", "focus-workspace next"),
+            ],
+            Self::Windows => &[],
+            Self::Window => &[("on-click-middle", "close-window")],
+        }
+    }
+}
+
+impl Widgets {
+    /// The chosen monitors or all of them
+    fn monitors(&self) -> Vec<Widget> {
+        if self.monitors.is_empty() {
+            Widget::value_variants().to_vec()
+        } else {
+            self.monitors.clone()
+        }
+    }
+
+    /// The command line running monitor in `format` for output if needed
+    fn command(&self, widget: Widget, format: &str) -> String {
+        let program = quote(&self.program);
+        let mut command = format!("{program} -f {format} {}", widget.name());
+        if let Some(output) = self.output.as_deref() {
+            if widget.per_output() {
+                command.push_str(&format!(" --output {}", quote(output)));
+            }
+        }
+        command
+    }
+
+    /// The name of module running monitor, like `niri-layout`
+    fn module(&self, widget: Widget) -> String {
+        match self.output.as_deref() {
+            Some(output) if widget.per_output() => {
+                format!("niri-{}#{output}", widget.name())
+            }
+            _ => format!("niri-{}", widget.name()),
+        }
+    }
+
+    /// Waybar modules as JSON object
+    fn waybar(&self) -> Value {
+        let mut modules = Map::new();
+        for widget in self.monitors() {
+            let mut module = json!({
+                "exec": self.command(widget, "waybar"),
+                "return-type": "json",
+            });
+            let program = quote(&self.program);
+            for (event, args) in widget.handlers() {
+                module[*event] = json!(format!("{program} {args}"));
+            }
+            modules.insert(format!("custom/{}", self.module(widget)), module);
+        }
+        Value::Object(modules)
+    }
+
+    /// Eww variables and widgets as yuck
+    fn eww(&self) -> String {
+        let mut yuck = String::new();
+        for widget in self.monitors() {
+            let name = self.module(widget).replace('#', "-");
+            let (format, initial) = match widget {
+                Widget::Windows => ("json", "[]"),
+                _ if widget.json() => ("json", "{}"),
+                _ => ("waybar", "{}"),
+            };
+            let command = string(&self.command(widget, format));
+            yuck.push_str(&format!(
+                "(deflisten {name} :initial {}\n  {command})\n\n",
+                string(initial),
+            ));
+            let body = self.eww_widget(widget, &name);
+            yuck.push_str(&format!("(defwidget {name} []\n  {body})\n\n"));
+        }
+        yuck.pop();
+        yuck
+    }
+
+    /// The example widget showing variable `name` of monitor
+    fn eww_widget(&self, widget: Widget, name: &str) -> String {
+        let program = quote(&self.program);
+        let label = match widget {
+            Widget::Windows => return self.eww_windows(name),
+            Widget::Window => format!(":text {{{name}.title ?: \"\"}}"),
+            Widget::FocusedWorkspace => {
+                format!(":text {{{name}.name ?: \"\"}}")
+            }
+            _ => format!(
+                ":markup {{{name}.text ?: \"\"}} \
+                 :tooltip {{{name}.tooltip ?: \"\"}}"
+            ),
+        };
+        let label = format!("(label :class \"{name}\" {label})");
+        let mut events = Vec::new();
+        let mut scroll = Vec::new();
+        for (event, args) in widget.handlers() {
+            let command = format!("{program} {args}");
+            match *event {
+                "on-click" => {
+                    events.push(format!(":onclick {}", string(&command)))
+                }
+                "on-click-middle" => {
+                    events.push(format!(":onmiddleclick {}", string(&command)))
+                }
+                _ => scroll.push(command),
+            }
+        }
+        if let [up, down] = scroll.as_slice() {
+            let command = format!("[ {{}} = up ] && {up} || {down}");
+            events.push(format!(":onscroll {}", string(&command)));
+        }
+        if events.is_empty() {
+            return label;
+        }
+        format!("(eventbox {}\n    {label})", events.join(" "))
+    }
+
+    /// The example widget with button per window in variable `name`
+    fn eww_windows(&self, name: &str) -> String {
+        let program = quote(&self.program);
+        let focus = format!("{program} action focus-window id=${{window.id}}");
+        let close = format!("{program} close-window ${{window.id}}");
+        format!(
+            "(box :class \"{name}\" :space-evenly false\n    \
+             (for window in {name}\n      \
+             (button :class {{window.focused ? \"focused\" : \"\"}}\n        \
+             :onclick {}\n        \
+             :onmiddleclick {}\n        \
+             (label :text {{window.app_name}} :tooltip {{window.title}}))))",
+            string(&focus),
+            string(&close),
+        )
+    }
+}
+
+impl Runner for Generate {
+    fn run(self, _niri: Niri, _format: Format, _config: Config) {
+        match self.target {
+            Target::Waybar(widgets) => {
+                let snippet = widgets.waybar();
+                let snippet = serde_json::to_string_pretty(&snippet).unwrap();
+                exit::write_stdout(format!("{snippet}\n").as_bytes())
+            }
+            Target::Eww(widgets) => {
+                exit::write_stdout(widgets.eww().as_bytes())
+            }
+        }
+    }
+}
+
+/// The yuck string literal of `text`
+fn string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote `word` for shell unless it consists of safe characters only
+fn quote(word: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+".contains(c);
+    if !word.is_empty() && word.chars().all(safe) {
+        word.to_owned()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
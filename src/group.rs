@@ -0,0 +1,243 @@
+use crate::{
+    exit::{self, Code},
+    monitor::{self, Monitor, Query},
+    waybar, Config, FocusedWindow, FocusedWorkspace, Format, Layout, Niri,
+    Pager, Runner, Windows, Workspaces,
+};
+
+use clap::{Parser, ValueEnum};
+use niri_ipc::Event;
+
+/// Combined monitors.
+///
+/// Produces to stdout single waybar message on every change of any of
+/// chosen monitors. The message text joins texts of monitors with
+/// separator, the tooltip joins their tooltips by lines and the class list
+/// merges their classes. The monitors use their default options and aliases
+/// from configuration file.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Group {
+    /// The monitors to combine in order of their texts
+    #[arg(value_enum, default_values = ["layout", "workspace", "window"])]
+    modules: Vec<Builtin>,
+
+    /// Separator between texts of monitors
+    #[arg(short, long, default_value = " ")]
+    separator: String,
+}
+
+/// The built-in monitors which may be combined
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    /// Keyboard layout monitor
+    Layout,
+    /// Workspace pager
+    Pager,
+    /// Workspaces buttons monitor
+    Workspaces,
+    /// Focused workspace monitor
+    Workspace,
+    /// Windows monitor
+    Windows,
+    /// Focused window monitor
+    Window,
+}
+
+impl Builtin {
+    /// The name of monitor as in command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Layout => "layout",
+            Self::Pager => "pager",
+            Self::Workspaces => "workspaces",
+            Self::Workspace => "workspace",
+            Self::Windows => "windows",
+            Self::Window => "window",
+        }
+    }
+
+    /// The monitor with default options
+    pub fn monitor(self, format: Format, config: &Config) -> Box<dyn Monitor> {
+        let args = [self.name()];
+        match self {
+            Self::Layout => {
+                Box::new(Layout::parse_from(args).monitor(format, config))
+            }
+            Self::Pager => {
+                Box::new(Pager::parse_from(args).monitor(format, config))
+            }
+            Self::Workspaces => {
+                Box::new(Workspaces::parse_from(args).monitor(format, config))
+            }
+            Self::Workspace => Box::new(
+                FocusedWorkspace::parse_from(args).monitor(format, config),
+            ),
+            Self::Windows => {
+                Box::new(Windows::parse_from(args).monitor(format, config))
+            }
+            Self::Window => Box::new(
+                FocusedWindow::parse_from(args).monitor(format, config),
+            ),
+        }
+    }
+}
+
+/// The built-in monitors tracking the same events, each with its name
+pub struct Modules {
+    modules: Vec<(Builtin, Box<dyn Monitor>)>,
+}
+
+impl Modules {
+    /// The monitors of `modules` printing in their formats
+    pub fn new(
+        modules: impl IntoIterator<Item = (Builtin, Format)>,
+        config: &Config,
+    ) -> Self {
+        let modules = modules
+            .into_iter()
+            .map(|(module, format)| (module, module.monitor(format, config)))
+            .collect();
+        Self { modules }
+    }
+
+    /// The monitors with their names in order
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (Builtin, &mut Box<dyn Monitor>)> {
+        self.modules.iter_mut().map(|(module, m)| (*module, m))
+    }
+
+    /// Apply `event` to all monitors, whether any of them could change
+    pub fn apply(&mut self, event: Event) -> bool {
+        let mut changed = false;
+        for (_, monitor) in &mut self.modules {
+            changed |= monitor.apply(event.clone());
+        }
+        changed
+    }
+
+    /// Whether `event` is consistent with states of all monitors
+    pub fn consistent(&self, event: &Event) -> bool {
+        self.modules.iter().all(|(_, m)| m.consistent(event))
+    }
+
+    /// The queries of all monitors without duplicates
+    pub fn queries(&self) -> Vec<Query> {
+        let mut queries = Vec::new();
+        for query in self.modules.iter().flat_map(|(_, m)| m.queries()) {
+            if !queries.contains(&query) {
+                queries.push(query);
+            }
+        }
+        queries
+    }
+
+    /// Apply reloaded configuration to all monitors
+    pub fn reload(&mut self, config: &Config) {
+        for (_, monitor) in &mut self.modules {
+            monitor.reload(config);
+        }
+    }
+}
+
+struct GroupRunner {
+    separator: String,
+    format: Format,
+    modules: Modules,
+}
+
+impl Runner for Group {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+impl Group {
+    /// Whether the combined message may be written in `format`
+    pub(crate) fn supports(format: &Format) -> bool {
+        matches!(
+            format,
+            Format::Waybar
+                | Format::Ironbar
+                | Format::I3blocks
+                | Format::I3blocksJson
+                | Format::Json
+                | Format::Jsonl
+        )
+    }
+
+    /// The monitor of combined monitors configured by these options
+    ///
+    /// Exits with usage error if `format` is not [supported](Self::supports).
+    pub(crate) fn monitor(
+        self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        if !Self::supports(&format) {
+            exit::fail(
+                Code::Usage,
+                "Only formats of waybar message are supported by group",
+            );
+        }
+        let modules = self.modules.iter().map(|m| (*m, Format::Waybar));
+        let modules = Modules::new(modules, config);
+        GroupRunner {
+            separator: self.separator,
+            format,
+            modules,
+        }
+    }
+}
+
+impl Monitor for GroupRunner {
+    fn apply(&mut self, event: Event) -> bool {
+        self.modules.apply(event)
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        self.modules.consistent(event)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        let mut texts = Vec::new();
+        let mut tooltips = Vec::new();
+        let mut class = Vec::new();
+        let mut line = Vec::new();
+        for (_, module) in self.modules.iter_mut() {
+            line.clear();
+            if !module.render(&mut line) {
+                continue;
+            }
+            let message: waybar::Message =
+                serde_json::from_slice(&line).unwrap();
+            if !message.text.is_empty() {
+                texts.push(message.text);
+            }
+            tooltips.extend(message.tooltip.filter(|t| !t.is_empty()));
+            for name in message.class {
+                if !class.contains(&name) {
+                    class.push(name);
+                }
+            }
+        }
+        let message = waybar::Message {
+            text: texts.join(&self.separator),
+            tooltip: Some(tooltips.join("\n")).filter(|t| !t.is_empty()),
+            class,
+            ..Default::default()
+        };
+        message.write_as(&self.format, out);
+        true
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        self.modules.queries()
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.modules.reload(config)
+    }
+}
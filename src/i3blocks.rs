@@ -0,0 +1,43 @@
+//! The output of i3blocks blocks.
+//!
+//! The block is either three lines with full text, short text and color, or
+//! single JSON object for blocks with `format=json`, see i3blocks(1). For
+//! persistent blocks only the JSON is suitable, as each line of plain output
+//! replaces the whole block there.
+
+use crate::waybar;
+
+use serde::Serialize;
+
+/// The state of block
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Block {
+    pub full_text: String,
+    pub short_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl Block {
+    /// Write block appended to `out` as JSON object or as lines
+    ///
+    /// The last line end is left to the caller.
+    pub fn write(&self, json: bool, out: &mut Vec<u8>) {
+        if json {
+            return serde_json::to_writer(out, self).unwrap();
+        }
+        let mut lines = vec![self.full_text.as_str(), &self.short_text];
+        lines.extend(self.color.as_deref());
+        out.extend_from_slice(lines.join("\n").as_bytes())
+    }
+}
+
+impl From<&waybar::Message> for Block {
+    fn from(message: &waybar::Message) -> Self {
+        Self {
+            full_text: message.text.clone(),
+            short_text: message.text.clone(),
+            color: None,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The icon of application
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Icon {
+    /// The icon name as specified in desktop entry
+    pub name: String,
+    /// The path to icon file if it was found in icon themes
+    pub path: Option<PathBuf>,
+}
+
+/// Resolver of application icons by app_id
+///
+/// The desktop entries are scanned once on creation, and the icon lookup
+/// results are cached.
+pub struct Icons {
+    themes: Vec<String>,
+    data_dirs: Vec<PathBuf>,
+    entries: HashMap<String, String>,
+    cache: HashMap<String, Option<Icon>>,
+}
+
+impl Icons {
+    /// Scan desktop entries and prepare to look icons up in `theme`
+    pub fn new(theme: &str) -> Self {
+        let mut themes = vec![theme.to_owned()];
+        if theme != "hicolor" {
+            themes.push("hicolor".into());
+        }
+        let data_dirs = data_dirs();
+        let mut entries = HashMap::new();
+        for dir in &data_dirs {
+            scan_applications(&dir.join("applications"), &mut entries);
+        }
+        Self {
+            themes,
+            data_dirs,
+            entries,
+            cache: Default::default(),
+        }
+    }
+
+    /// Find icon of application with given app_id
+    pub fn lookup(&mut self, app_id: &str) -> Option<Icon> {
+        if let Some(icon) = self.cache.get(app_id) {
+            return icon.clone();
+        }
+        let icon = self.resolve(app_id);
+        self.cache.insert(app_id.to_owned(), icon.clone());
+        icon
+    }
+
+    fn resolve(&self, app_id: &str) -> Option<Icon> {
+        if app_id.is_empty() {
+            return None;
+        }
+        let id = app_id.to_lowercase();
+        let short = id.rsplit('.').next().unwrap_or(&id);
+        let name = self
+            .entries
+            .get(&id)
+            .or_else(|| self.entries.get(short))
+            .cloned()
+            .unwrap_or_else(|| app_id.to_owned());
+        let path = self.find_file(&name);
+        Some(Icon { name, path })
+    }
+
+    fn find_file(&self, name: &str) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return path.exists().then(|| path.to_owned());
+        }
+        for theme in &self.themes {
+            for dir in &self.data_dirs {
+                let found = find_in_theme(&dir.join("icons").join(theme), name);
+                if found.is_some() {
+                    return found;
+                }
+            }
+        }
+        self.data_dirs
+            .iter()
+            .map(|dir| dir.join("pixmaps"))
+            .chain([PathBuf::from("/usr/share/pixmaps")])
+            .find_map(|dir| find_image(&dir, name))
+    }
+}
+
+/// The XDG data directories in order of priority
+fn data_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("XDG_DATA_HOME").map(PathBuf::from).or_else(|| {
+        env::var_os("HOME").map(|h| Path::new(&h).join(".local/share"))
+    });
+    let dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    home.into_iter()
+        .chain(dirs.split(':').map(PathBuf::from))
+        .collect()
+}
+
+/// Collect icons of desktop entries by desktop id and startup window class
+///
+/// The entries already collected from directories with higher priority are
+/// kept.
+fn scan_applications(dir: &Path, entries: &mut HashMap<String, String>) {
+    let Ok(files) = fs::read_dir(dir) else {
+        return;
+    };
+    for file in files.flatten() {
+        let path = file.path();
+        if path.extension().is_none_or(|ext| ext != "desktop") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Some((icon, wm_class)) = parse_desktop_entry(&content) else {
+            continue;
+        };
+        if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+            let id = id.to_lowercase();
+            entries.entry(id).or_insert_with(|| icon.clone());
+        }
+        if let Some(wm_class) = wm_class {
+            entries.entry(wm_class.to_lowercase()).or_insert(icon);
+        }
+    }
+}
+
+/// Parse `Icon` and `StartupWMClass` keys of desktop entry
+fn parse_desktop_entry(content: &str) -> Option<(String, Option<String>)> {
+    let mut in_entry = false;
+    let mut icon = None;
+    let mut wm_class = None;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        match line.split_once('=') {
+            Some(("Icon", value)) => icon = Some(value.trim().to_owned()),
+            Some(("StartupWMClass", value)) => {
+                wm_class = Some(value.trim().to_owned())
+            }
+            _ => (),
+        }
+    }
+    Some((icon.filter(|icon| !icon.is_empty())?, wm_class))
+}
+
+/// Find application icon in theme directory preferring scalable and then
+/// the largest icons
+fn find_in_theme(theme: &Path, name: &str) -> Option<PathBuf> {
+    let mut sizes = fs::read_dir(theme)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    sizes.sort_by_key(|size| {
+        let px = size.split('x').next().and_then(|s| s.parse::<u32>().ok());
+        match (size.as_str(), px) {
+            ("scalable", _) => 0,
+            (_, Some(px)) => u32::MAX - px,
+            _ => u32::MAX,
+        }
+    });
+    sizes
+        .iter()
+        .find_map(|size| find_image(&theme.join(size).join("apps"), name))
+}
+
+fn find_image(dir: &Path, name: &str) -> Option<PathBuf> {
+    ["svg", "png", "xpm"]
+        .iter()
+        .map(|ext| dir.join(format!("{name}.{ext}")))
+        .find(|path| path.is_file())
+}
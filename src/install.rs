@@ -0,0 +1,164 @@
+//! The installation of niri-glue into user session.
+
+use crate::{
+    exit::{self, Code},
+    Config, Format, Niri, Runner,
+};
+
+use clap::Parser;
+use std::{
+    env, fs, io,
+    path::{self, Path, PathBuf},
+};
+
+/// The background commands run as services with their descriptions
+const SERVICES: &[(&str, &str)] =
+    &[("restore-focus", "niri-glue focus keeper")];
+
+/// Session installer.
+///
+/// Writes systemd user unit running the focus keeper (`restore-focus`)
+/// along with niri session, or removes it. The unit is written to
+/// `$XDG_CONFIG_HOME/systemd/user`, enable it with `systemctl --user enable
+/// --now` afterwards. The units of monitors are not installed, as monitors
+/// are run by bars reading their output.
+///
+/// The unit is given the socket and configuration paths set for this run.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Install {
+    /// Install systemd user unit of the focus keeper
+    #[arg(long, required = true)]
+    systemd: bool,
+
+    /// Remove the installed unit instead
+    #[arg(long)]
+    remove: bool,
+
+    /// Path to niri-glue run by units, the running executable by default
+    #[arg(long)]
+    program: Option<PathBuf>,
+}
+
+impl Runner for Install {
+    fn run(self, niri: Niri, _format: Format, config: Config) {
+        let Some(dir) = units_dir() else {
+            exit::fail(
+                Code::Failure,
+                "Neither XDG_CONFIG_HOME nor HOME is set",
+            );
+        };
+        let program = self.program.clone().or_else(|| env::current_exe().ok());
+        let Some(program) = program else {
+            exit::fail(Code::Failure, "Failed to find path to niri-glue");
+        };
+        for (command, description) in SERVICES {
+            let path = dir.join(format!("niri-glue-{command}.service"));
+            if self.remove {
+                remove(&path, &niri, &config);
+                continue;
+            }
+            let unit = unit(&program, &niri, &config, command, description);
+            if niri.dry_run() {
+                let header = format!("# {}\n", path.display());
+                exit::write_stdout((header + &unit).as_bytes());
+                continue;
+            }
+            let written =
+                fs::create_dir_all(&dir).and_then(|_| fs::write(&path, unit));
+            if let Err(e) = written {
+                exit::fail(
+                    Code::Failure,
+                    format!("Failed to write {}: {e}", path.display()),
+                );
+            }
+            if !config.quiet() {
+                let report = format!("Installed {}\n", path.display());
+                exit::write_stdout(report.as_bytes());
+            }
+        }
+    }
+}
+
+/// The service unit running `command` of `program` within niri session
+///
+/// The unit gets the paths of niri socket and configuration file as they
+/// are set for `niri` and `config`.
+fn unit(
+    program: &Path,
+    niri: &Niri,
+    config: &Config,
+    command: &str,
+    description: &str,
+) -> String {
+    let mut exec = quote(program);
+    if let Some(path) = &niri.path {
+        exec.push_str(&format!(" --path {}", quote(&absolute(path))));
+    }
+    if let Some(path) = config.given_path() {
+        exec.push_str(&format!(" --config {}", quote(&absolute(path))));
+    }
+    // Niri may still be starting when the session target is reached
+    exec.push_str(&format!(" --retries 10 {command}"));
+    format!(
+        "[Unit]\n\
+         Description={description}\n\
+         PartOf=graphical-session.target\n\
+         After=graphical-session.target\n\
+         Requisite=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={exec}\n\
+         Restart=on-failure\n\
+         RestartSec=1\n\
+         \n\
+         [Install]\n\
+         WantedBy=niri.service\n"
+    )
+}
+
+/// Remove unit at `path` if it is installed
+fn remove(path: &Path, niri: &Niri, config: &Config) {
+    if niri.dry_run() {
+        let report = format!("Would remove {}\n", path.display());
+        return exit::write_stdout(report.as_bytes());
+    }
+    match fs::remove_file(path) {
+        Ok(()) if !config.quiet() => {
+            let report = format!("Removed {}\n", path.display());
+            exit::write_stdout(report.as_bytes())
+        }
+        Ok(()) => (),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(e) => exit::fail(
+            Code::Failure,
+            format!("Failed to remove {}: {e}", path.display()),
+        ),
+    }
+}
+
+/// The `path` quoted for command lines of systemd if needed
+fn quote(path: &Path) -> String {
+    let path = path.display().to_string();
+    if path.contains(char::is_whitespace) {
+        format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        path
+    }
+}
+
+/// The `path` made absolute, as units are run from another directory
+fn absolute(path: &Path) -> PathBuf {
+    path::absolute(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// The directory of systemd user units of user
+fn units_dir() -> Option<PathBuf> {
+    let dir =
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|h| Path::new(&h).join(".config"))
+            })?;
+    Some(dir.join("systemd").join("user"))
+}
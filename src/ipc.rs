@@ -0,0 +1,137 @@
+//! The blocking communication over niri socket with timeouts.
+//!
+//! Mirrors the socket of `niri_ipc`, which gives no access to its stream.
+
+#[cfg(feature = "actions")]
+use niri_ipc::WorkspaceReferenceArg;
+use niri_ipc::{Event, Reply};
+use serde::Serialize;
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::Shutdown,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// The actions of newer niri, which are missing in `niri_ipc` in use
+///
+/// They are serialized the same way as [niri_ipc::Action], so the older niri
+/// rejects them as unknown.
+#[cfg(feature = "actions")]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Toggle the overview of workspaces
+    ToggleOverview {},
+    /// Open the overview of workspaces
+    OpenOverview {},
+    /// Close the overview of workspaces
+    CloseOverview {},
+    /// Move workspace to the `index` on its output
+    MoveWorkspaceToIndex {
+        /// The index starting from 1
+        index: usize,
+        /// The workspace to move, the focused one if not set
+        reference: Option<WorkspaceReferenceArg>,
+    },
+    /// Toggle between normal and tabbed display of the focused column
+    ToggleColumnTabbedDisplay {},
+    /// Move workspace to the `output`
+    MoveWorkspaceToMonitor {
+        /// The name of output
+        output: String,
+        /// The workspace to move, the focused one if not set
+        reference: Option<WorkspaceReferenceArg>,
+    },
+}
+
+/// The request of niri with action of [Action]
+#[cfg(feature = "actions")]
+#[derive(Serialize, Debug, Clone)]
+pub enum Request {
+    /// Perform the action
+    Action(Action),
+}
+
+/// The connection to niri socket for single request
+pub struct Socket {
+    stream: UnixStream,
+}
+
+impl Socket {
+    /// Connect to niri socket at `path` within `timeout`
+    ///
+    /// The following writes and reads fail with [ErrorKind::TimedOut] if they
+    /// take longer than `timeout`.
+    pub fn connect_to(
+        path: &Path,
+        timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        let stream = match timeout {
+            Some(timeout) => connect_within(path.to_owned(), timeout)?,
+            None => UnixStream::connect(path)?,
+        };
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)?;
+        Ok(Self { stream })
+    }
+
+    /// Send request to niri and return the reply
+    ///
+    /// The returned function reads the following events after
+    /// [EventStream](niri_ipc::Request::EventStream) request.
+    pub fn send(
+        self,
+        request: impl Serialize,
+    ) -> io::Result<(Reply, impl FnMut() -> io::Result<Event>)> {
+        let Self { mut stream } = self;
+        let mut buf = serde_json::to_string(&request)?;
+        stream.write_all(buf.as_bytes()).map_err(timed_out)?;
+        stream.shutdown(Shutdown::Write)?;
+        let mut reader = BufReader::new(stream);
+        buf.clear();
+        read_line(&mut reader, &mut buf)?;
+        let reply = serde_json::from_str(&buf)?;
+        let events = move || {
+            buf.clear();
+            read_line(&mut reader, &mut buf)?;
+            Ok(serde_json::from_str(&buf)?)
+        };
+        Ok((reply, events))
+    }
+}
+
+/// Connect in background thread, which is abandoned after `timeout`
+///
+/// The unix sockets block on connect while compositor does not accept
+/// connections and its backlog is full.
+fn connect_within(path: PathBuf, timeout: Duration) -> io::Result<UnixStream> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(UnixStream::connect(path));
+    });
+    rx.recv_timeout(timeout).unwrap_or_else(|_| {
+        Err(io::Error::new(ErrorKind::TimedOut, "connection timed out"))
+    })
+}
+
+fn read_line(reader: &mut impl BufRead, buf: &mut String) -> io::Result<()> {
+    match reader.read_line(buf).map_err(timed_out)? {
+        0 => Err(ErrorKind::UnexpectedEof.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The error of expired socket timeout reported as [ErrorKind::TimedOut]
+///
+/// The sockets report it as [ErrorKind::WouldBlock] on Linux.
+fn timed_out(error: io::Error) -> io::Error {
+    match error.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+            io::Error::new(ErrorKind::TimedOut, "operation timed out")
+        }
+        _ => error,
+    }
+}
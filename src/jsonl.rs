@@ -0,0 +1,57 @@
+//! Timestamped JSON records for logging pipelines.
+
+#[cfg(feature = "actions")]
+use std::mem;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Append the record of JSON `data` of `kind` to `out`
+///
+/// The record is JSON object with RFC 3339 UTC time, like
+/// `{"time":"2024-12-24T10:00:00.000Z","type":"layout","data":{...}}`.
+pub fn record(kind: &str, data: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(b"{\"time\":\"");
+    out.extend_from_slice(rfc3339(SystemTime::now()).as_bytes());
+    out.extend_from_slice(b"\",\"type\":");
+    serde_json::to_writer(&mut *out, kind).unwrap();
+    out.extend_from_slice(b",\"data\":");
+    out.extend_from_slice(data);
+    out.push(b'}');
+}
+
+/// Replace JSON in `out` with its record of `kind`
+#[cfg(feature = "actions")]
+pub fn wrap(kind: &str, out: &mut Vec<u8>) {
+    let data = mem::take(out);
+    record(kind, &data, out);
+}
+
+/// The UTC time in RFC 3339 format with milliseconds
+fn rfc3339(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (days, secs) = (secs / 86400, secs % 86400);
+    let (year, month, day) = civil(days as i64);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        since.subsec_millis()
+    )
+}
+
+/// The date of `days` since the Unix epoch
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
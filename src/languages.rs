@@ -0,0 +1,235 @@
+//! Built-in knowledge of languages of keyboard layouts.
+
+use std::env;
+
+/// The language names used in xkb layout descriptions with their ISO 639-1
+/// codes
+const LANGUAGES: &[(&str, &str)] = &[
+    ("Albanian", "sq"),
+    ("Amharic", "am"),
+    ("Arabic", "ar"),
+    ("Armenian", "hy"),
+    ("Azerbaijani", "az"),
+    ("Bashkirian", "ba"),
+    ("Belarusian", "be"),
+    ("Bengali", "bn"),
+    ("Bosnian", "bs"),
+    ("Bulgarian", "bg"),
+    ("Burmese", "my"),
+    ("Catalan", "ca"),
+    ("Chinese", "zh"),
+    ("Croatian", "hr"),
+    ("Czech", "cs"),
+    ("Danish", "da"),
+    ("Dhivehi", "dv"),
+    ("Dutch", "nl"),
+    ("English", "en"),
+    ("Esperanto", "eo"),
+    ("Estonian", "et"),
+    ("Faroese", "fo"),
+    ("Filipino", "tl"),
+    ("Finnish", "fi"),
+    ("French", "fr"),
+    ("Georgian", "ka"),
+    ("German", "de"),
+    ("Greek", "el"),
+    ("Hebrew", "he"),
+    ("Hindi", "hi"),
+    ("Hungarian", "hu"),
+    ("Icelandic", "is"),
+    ("Indonesian", "id"),
+    ("Irish", "ga"),
+    ("Italian", "it"),
+    ("Japanese", "ja"),
+    ("Kazakh", "kk"),
+    ("Khmer", "km"),
+    ("Korean", "ko"),
+    ("Kurdish", "ku"),
+    ("Kyrgyz", "ky"),
+    ("Lao", "lo"),
+    ("Latvian", "lv"),
+    ("Lithuanian", "lt"),
+    ("Macedonian", "mk"),
+    ("Malay", "ms"),
+    ("Maltese", "mt"),
+    ("Maori", "mi"),
+    ("Mongolian", "mn"),
+    ("Nepali", "ne"),
+    ("Norwegian", "no"),
+    ("Pashto", "ps"),
+    ("Persian", "fa"),
+    ("Polish", "pl"),
+    ("Portuguese", "pt"),
+    ("Romanian", "ro"),
+    ("Russian", "ru"),
+    ("Serbian", "sr"),
+    ("Sinhala", "si"),
+    ("Slovak", "sk"),
+    ("Slovenian", "sl"),
+    ("Spanish", "es"),
+    ("Swahili", "sw"),
+    ("Swedish", "sv"),
+    ("Tajik", "tg"),
+    ("Tamil", "ta"),
+    ("Tatar", "tt"),
+    ("Telugu", "te"),
+    ("Thai", "th"),
+    ("Tibetan", "bo"),
+    ("Turkish", "tr"),
+    ("Turkmen", "tk"),
+    ("Ukrainian", "uk"),
+    ("Urdu", "ur"),
+    ("Uzbek", "uz"),
+    ("Vietnamese", "vi"),
+    ("Welsh", "cy"),
+    ("Wolof", "wo"),
+];
+
+/// The ISO 639-1 code of language of layout with xkb description `name`
+///
+/// The description is expected to start with the language name, like
+/// `English (US)` or `Portuguese (Brazil)`.
+pub fn iso_code(name: &str) -> Option<&'static str> {
+    let language = name.split([' ', '(', ',']).next()?;
+    LANGUAGES
+        .iter()
+        .find(|(lang, _)| lang.eq_ignore_ascii_case(language))
+        .map(|(_, code)| *code)
+}
+
+/// The locales of built-in translations
+const LOCALES: [&str; 5] = ["ru", "uk", "de", "fr", "es"];
+
+/// The translations of language names to each of [LOCALES]
+const TRANSLATIONS: &[(&str, [&str; 5])] = &[
+    (
+        "Arabic",
+        ["Арабская", "Арабська", "Arabisch", "Arabe", "Árabe"],
+    ),
+    (
+        "Belarusian",
+        [
+            "Белорусская",
+            "Білоруська",
+            "Belarussisch",
+            "Biélorusse",
+            "Bielorruso",
+        ],
+    ),
+    (
+        "Chinese",
+        ["Китайская", "Китайська", "Chinesisch", "Chinois", "Chino"],
+    ),
+    (
+        "Czech",
+        ["Чешская", "Чеська", "Tschechisch", "Tchèque", "Checo"],
+    ),
+    (
+        "English",
+        ["Английская", "Англійська", "Englisch", "Anglais", "Inglés"],
+    ),
+    (
+        "French",
+        [
+            "Французская",
+            "Французька",
+            "Französisch",
+            "Français",
+            "Francés",
+        ],
+    ),
+    (
+        "German",
+        ["Немецкая", "Німецька", "Deutsch", "Allemand", "Alemán"],
+    ),
+    (
+        "Greek",
+        ["Греческая", "Грецька", "Griechisch", "Grec", "Griego"],
+    ),
+    (
+        "Hebrew",
+        ["Иврит", "Іврит", "Hebräisch", "Hébreu", "Hebreo"],
+    ),
+    (
+        "Italian",
+        [
+            "Итальянская",
+            "Італійська",
+            "Italienisch",
+            "Italien",
+            "Italiano",
+        ],
+    ),
+    (
+        "Japanese",
+        ["Японская", "Японська", "Japanisch", "Japonais", "Japonés"],
+    ),
+    (
+        "Kazakh",
+        ["Казахская", "Казахська", "Kasachisch", "Kazakh", "Kazajo"],
+    ),
+    (
+        "Korean",
+        ["Корейская", "Корейська", "Koreanisch", "Coréen", "Coreano"],
+    ),
+    (
+        "Polish",
+        ["Польская", "Польська", "Polnisch", "Polonais", "Polaco"],
+    ),
+    (
+        "Portuguese",
+        [
+            "Португальская",
+            "Португальська",
+            "Portugiesisch",
+            "Portugais",
+            "Portugués",
+        ],
+    ),
+    (
+        "Russian",
+        ["Русская", "Російська", "Russisch", "Russe", "Ruso"],
+    ),
+    (
+        "Spanish",
+        ["Испанская", "Іспанська", "Spanisch", "Espagnol", "Español"],
+    ),
+    (
+        "Turkish",
+        ["Турецкая", "Турецька", "Türkisch", "Turc", "Turco"],
+    ),
+    (
+        "Ukrainian",
+        [
+            "Украинская",
+            "Українська",
+            "Ukrainisch",
+            "Ukrainien",
+            "Ucraniano",
+        ],
+    ),
+];
+
+/// The language of user's locale from `LC_ALL`, `LC_MESSAGES` or `LANG`
+pub fn locale() -> Option<String> {
+    let value = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())?;
+    let language = value.split(['_', '.', '@']).next()?;
+    Some(language.to_lowercase()).filter(|l| l != "c" && l != "posix")
+}
+
+/// The layout description `name` with language translated to `locale`
+///
+/// Only the leading language name is translated, like `Немецкая (Austria)`
+/// for `German (Austria)`.
+pub fn translate(name: &str, locale: &str) -> Option<String> {
+    let column = LOCALES.iter().position(|l| *l == locale)?;
+    let end = name.find([' ', '(', ',']).unwrap_or(name.len());
+    let (language, rest) = name.split_at(end);
+    let (_, translations) = TRANSLATIONS
+        .iter()
+        .find(|(lang, _)| lang.eq_ignore_ascii_case(language))?;
+    Some(format!("{}{rest}", translations[column]))
+}
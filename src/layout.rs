@@ -1,11 +1,28 @@
-use crate::{Runner, Format};
+use crate::{Config, Runner, Format, Niri};
+use crate::{waybar, xkb, yambar};
+#[cfg(feature = "actions")]
+use crate::exit::{self, Code};
+#[cfg(any(feature = "monitors", feature = "actions"))]
+use crate::languages;
+#[cfg(feature = "monitors")]
+use crate::monitor::{self, Monitor, Query};
+#[cfg(feature = "actions")]
+use crate::jsonl;
 
-use std::{io::{self, Error}, collections::HashMap};
+use std::io::{self, Error};
+use std::collections::HashMap;
 use clap::Parser;
-use niri_ipc::{socket::Socket, Request, Event, KeyboardLayouts, Action, LayoutSwitchTarget};
-use regex::Regex;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+use clap::ValueEnum;
+use niri_ipc::KeyboardLayouts;
+#[cfg(feature = "monitors")]
+use niri_ipc::Event;
+#[cfg(feature = "actions")]
+use niri_ipc::{Request, Response, Action, LayoutSwitchTarget};
 
-fn parse_key_val(s: &str) -> Result<(String, String), Error>
+/// Parse argument of form `KEY=value`
+#[cfg(any(feature = "monitors", feature = "actions"))]
+pub fn parse_key_val(s: &str) -> Result<(String, String), Error>
 {
     if let Some((var, value)) = s.split_once('=') {
         Ok((var.to_owned(), value.to_owned()))
@@ -18,125 +35,430 @@ fn parse_key_val(s: &str) -> Result<(String, String), Error>
 /// Keyboard layout monitor.
 ///
 /// Produces to stdout messages about keyboard layout actions.
+#[cfg(any(feature = "monitors", feature = "actions"))]
 #[derive(Parser, Debug, Clone)]
 #[command(about, long_about)]
 pub struct Layout {
     /// Aliases for languages
     ///
-    /// Each argument must have format `Source=ALIAS`
+    /// Each argument must have format `Source=ALIAS`. The source is either
+    /// part of layout name, or its xkb code like `us` or `us(intl)`.
     #[arg(short, long="alias", value_parser = parse_key_val, number_of_values = 1)]
-    aliases: Vec<(String, String)>
+    aliases: Vec<(String, String)>,
+
+    /// The part of layout name to display
+    #[arg(short, long, value_enum, default_value = "auto")]
+    display: LayoutDisplay,
+
+    /// Display ISO 639 language codes of layouts
+    ///
+    /// The layouts are recognized by built-in table of languages, aliases
+    /// still take precedence.
+    #[arg(long)]
+    short_codes: bool,
+
+    /// Translate language names of layouts to the language of locale
+    ///
+    /// The locale is taken from `LC_ALL`, `LC_MESSAGES` or `LANG`. The
+    /// built-in translations cover common languages in Russian, Ukrainian,
+    /// German, French and Spanish. Aliases still take precedence.
+    #[arg(long)]
+    localize: bool,
 }
 
-struct LayoutRunner {
+/// The way to display layout name of form `Name (variant)`
+#[cfg(any(feature = "monitors", feature = "actions"))]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum LayoutDisplay {
+    /// Alias of variant or name, or variant, or name, whichever found first
+    Auto,
+    /// Variant if present, name otherwise
+    Short,
+    /// Full name
+    Full,
+    /// Short and full names as `SHORT: FULL`
+    Both,
+}
+
+/// The rules of displaying keyboard layout names configured by [Layout]
+#[cfg(any(feature = "monitors", feature = "actions"))]
+pub struct LayoutNames {
+    display: LayoutDisplay,
+    short_codes: bool,
+    /// The aliases with lowercase sources
     aliases: HashMap<String, String>,
+    /// The language to translate layout names to
+    locale: Option<String>,
+    registry: xkb::Registry,
+}
+
+#[cfg(feature = "monitors")]
+struct LayoutRunner {
+    config: Layout,
+    format: Format,
+    markup: waybar::Markup,
+    naming: LayoutNames,
+    /// The layout names as reported by niri
+    names: Vec<String>,
     layouts: KeyboardLayouts,
-    re: Regex,
 }
 
+#[cfg(feature = "monitors")]
 impl Runner for Layout {
-    fn run(self, socket: Socket, format: Format) {
-        let runner = LayoutRunner::new(self, format);
-        runner.run(socket)
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+#[cfg(any(feature = "monitors", feature = "actions"))]
+impl Layout {
+    /// The monitor of keyboard layouts configured by these options
+    #[cfg(feature = "monitors")]
+    pub(crate) fn monitor(self, format: Format, config: &Config) -> impl Monitor
+    {
+        LayoutRunner::new(self, format, config)
+    }
+
+    /// The rules of displaying layout names with aliases from `config`
+    ///
+    /// The aliases given in these options take precedence.
+    pub fn names(&self, config: &Config) -> LayoutNames
+    {
+        LayoutNames {
+            display: self.display,
+            short_codes: self.short_codes,
+            aliases: config.aliases.iter().chain(self.aliases.iter().map(|(k, v)| (k, v)))
+                .map(|(k,v)| (k.to_lowercase(), v.clone())).collect(),
+            locale: if self.localize { languages::locale() } else { None },
+            registry: xkb::Registry::load(),
+        }
     }
 }
 
+#[cfg(feature = "monitors")]
 impl LayoutRunner {
-    fn new(config: Layout, format: Format) -> Self {
-        assert_eq!(format, Format::Waybar, "Only Waybar format of layout supported");
-        let aliases = config.aliases.into_iter().map(|(k,v)| (k.to_lowercase(), v)).collect::<HashMap<String, String>>();
+    fn new(config: Layout, format: Format, global: &Config) -> Self {
         Self {
-            aliases,
+            naming: config.names(global),
+            config,
+            markup: global.markup(&format),
+            format,
+            names: Default::default(),
             layouts: KeyboardLayouts{ names: Default::default(), current_idx: 0 },
-            re: Regex::new(r"^(?<full>\S+)\s*(\((?<alias>\S+)\))?$").unwrap(),
         }
     }
 
-    fn process_event(&mut self, ev:Event)
+    /// Take aliases from configuration, command line ones take precedence
+    fn configure(&mut self, global: &Config)
     {
-        match ev {
-            Event::KeyboardLayoutsChanged { keyboard_layouts } => self.changed(keyboard_layouts),
-            Event::KeyboardLayoutSwitched { idx } => self.switched(idx),
-            _ => ()
-        }
+        self.markup = global.markup(&self.format);
+        self.naming = self.config.names(global);
+        self.layouts.names = self.names.iter().map(|name|self.naming.display(name)).collect();
     }
 
     fn changed(&mut self, layouts: KeyboardLayouts)
     {
-        self.layouts.names = layouts.names.into_iter().map(|name|self.alias_layout(&name)).collect();
+        self.layouts.names = layouts.names.iter().map(|name|self.naming.display(name)).collect();
+        self.names = layouts.names;
         self.switched(layouts.current_idx)
     }
 
     fn switched(&mut self, idx: u8)
     {
-        if let Some(layout) = self.layouts.names.get(usize::from(idx)) {
-            println!("{{ \"text\": \"{layout}\", \"class\": \"layout\" }}");
+        if usize::from(idx) < self.layouts.names.len() {
             self.layouts.current_idx = idx;
         }
     }
+}
 
-    fn alias_layout(&self, name: &str) -> String
+#[cfg(any(feature = "monitors", feature = "actions"))]
+impl LayoutNames {
+    /// The name of layout to display according to display option
+    pub fn display(&self, name: &str) -> String
     {
-        if let Some(caps) = self.re.captures(name) {
-            let mut alias: Option<&str> = caps.name("alias").map(|m| m.as_str());
-            if alias == Some("") {
-                alias = None
-            }
-            let alias_alias = self.alias_for(alias);
-            if let Some(alias_alias) = alias_alias {
-                alias_alias.into()
-            } else {
-                let mut full = caps.name("full").map(|m| m.as_str());
-                if full == Some("") {
-                    full = None
-                }
-                let full_alias = self.alias_for(full);
-
-                if let Some(full_alias) = full_alias {
-                    full_alias.into()
-                } else {
-                    if let Some(alias) = alias {
-                        alias.into()
-                    } else {
-                        if let Some(full) = full {
-                            full.into()
-                        } else {
-                            name.into()
-                        }
-                    }
-                }
+        if let Some(alias) = self.code_alias(name) {
+            return alias.into();
+        }
+        if self.short_codes && !self.has_alias(name) {
+            if let Some(code) = languages::iso_code(name) {
+                return code.into();
             }
-        } else {
-            name.into()
+        }
+        let translated = self.locale.as_deref().filter(|_| !self.has_alias(name))
+            .and_then(|locale| languages::translate(name, locale));
+        let name = translated.as_deref().unwrap_or(name);
+        let aliased = |part: &str| alias_for(&self.aliases, Some(part)).unwrap_or(part).to_owned();
+        let short = || match split_layout(name) {
+            Some((full, alias)) => aliased(alias.unwrap_or(full)),
+            None => aliased(name),
+        };
+        match self.display {
+            LayoutDisplay::Auto => alias_layout(&self.aliases, name),
+            LayoutDisplay::Short => short(),
+            LayoutDisplay::Full => aliased(name),
+            LayoutDisplay::Both => format!("{}: {}", short(), aliased(name)),
         }
     }
-    fn alias_for<'a>(&'a self, name: Option<&'a str>) -> Option<&'a str>
+
+    /// Whether any user alias applies to layout name or its parts
+    fn has_alias(&self, name: &str) -> bool
     {
-        if let Some(name) = name {
-            self.aliases.get(&name.to_lowercase()).map(String::as_str)
+        let (full, alias) = split_layout(name).unwrap_or((name, None));
+        [Some(name), Some(full), alias].into_iter().any(|part| alias_for(&self.aliases, part).is_some())
+            || self.code_alias(name).is_some()
+    }
+
+    /// The alias of layout by its xkb code, like `us(intl)` or `us`
+    fn code_alias(&self, name: &str) -> Option<&str>
+    {
+        let code = self.registry.code(name)?;
+        let layout = code.split('(').next();
+        alias_for(&self.aliases, Some(code)).or_else(|| alias_for(&self.aliases, layout))
+    }
+}
+
+/// The name of layout to display according to `aliases` with lowercase keys
+///
+/// The alias of variant takes precedence over alias of name, like for
+/// `English (US)` the alias of `us` is taken first, then of `english`.
+/// Without aliases the variant or the whole name is displayed.
+pub fn alias_layout(aliases: &HashMap<String, String>, name: &str) -> String
+{
+    if let Some((full, alias)) = split_layout(name) {
+        let alias_alias = alias_for(aliases, alias);
+        if let Some(alias_alias) = alias_alias {
+            alias_alias.into()
         } else {
-            None
+            let full_alias = alias_for(aliases, Some(full));
+
+            if let Some(full_alias) = full_alias {
+                full_alias.into()
+            } else {
+                alias.unwrap_or(full).into()
+            }
         }
+    } else {
+        name.into()
+    }
+}
+
+fn alias_for<'a>(aliases: &'a HashMap<String, String>, name: Option<&'a str>) -> Option<&'a str>
+{
+    if let Some(name) = name {
+        aliases.get(&name.to_lowercase()).map(String::as_str)
+    } else {
+        None
+    }
+}
+
+/// Split layout name of form `Name (variant)` to name and variant
+///
+/// Both parts must not contain whitespaces, the variant is optional.
+fn split_layout(name: &str) -> Option<(&str, Option<&str>)>
+{
+    let name = name.trim_end();
+    let (full, rest) = name.split_once(char::is_whitespace).unwrap_or((name, ""));
+    if full.is_empty() {
+        return None;
+    }
+    let rest = rest.trim_start();
+    if rest.is_empty() {
+        return Some((full, None));
+    }
+    let alias = rest.strip_prefix('(')?.strip_suffix(')')?;
+    if alias.is_empty() || alias.contains(char::is_whitespace) {
+        return None;
     }
-    fn run(mut self, socket: Socket) {
-        let (_, mut functor) = socket.send(Request::EventStream).unwrap();
-        loop {
-            let event = functor().unwrap();
-            self.process_event(event);
+    Some((full, Some(alias)))
+}
+
+#[cfg(feature = "monitors")]
+impl Monitor for LayoutRunner {
+    fn apply(&mut self, ev: Event) -> bool
+    {
+        match ev {
+            Event::KeyboardLayoutsChanged { keyboard_layouts } => self.changed(keyboard_layouts),
+            Event::KeyboardLayoutSwitched { idx } => self.switched(idx),
+            _ => return false
         }
+        true
+    }
+
+    fn consistent(&self, ev: &Event) -> bool
+    {
+        monitor::known_layouts(Some(&self.layouts), ev)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool
+    {
+        let Some(layout) = self.layouts.names.get(usize::from(self.layouts.current_idx)) else {
+            return false;
+        };
+        let Some(line) = render_layout(layout, self.layouts.current_idx, &self.format, &self.markup) else {
+            return false;
+        };
+        out.extend_from_slice(line.as_bytes());
+        true
+    }
+
+    fn queries(&self) -> Vec<Query>
+    {
+        vec![Query::KeyboardLayouts]
+    }
+
+    fn reload(&mut self, config: &Config)
+    {
+        self.configure(config)
+    }
+}
+
+#[cfg(feature = "actions")]
+fn parse_pair(s: &str) -> Result<(String, String), Error>
+{
+    match s.split_once(',') {
+        Some((first, second)) if !first.is_empty() && !second.is_empty() => Ok((first.to_owned(), second.to_owned())),
+        _ => Err(Error::new(io::ErrorKind::InvalidInput,
+                format!("invalid FIRST,SECOND: `{}`", s))),
     }
 }
 
 /// Keyboard layout switcher.
 ///
 /// Switches the keyboard layout.
+#[cfg(feature = "actions")]
 #[derive(Parser, Debug, Clone)]
 #[command(about, long_about)]
 pub struct SwitchLayout {
+    /// Switch between two given layouts only
+    ///
+    /// Switches to the second layout if the first one is current, and to the
+    /// first one otherwise. Each layout is matched case-insensitively by full
+    /// name, its name or variant part (`English` or `US` for `English (US)`),
+    /// or by prefix of full name.
+    #[arg(long, value_parser = parse_pair, value_name = "FIRST,SECOND")]
+    toggle_between: Option<(String, String)>,
+
+    /// Print the layout after switching
+    ///
+    /// The layout is printed like `layout` monitor does with the naming
+    /// options below and aliases from configuration file.
+    #[arg(long)]
+    print: bool,
+
+    #[command(flatten)]
+    names: Layout,
 }
 
+#[cfg(feature = "actions")]
 impl Runner for SwitchLayout {
-    fn run(self, socket: Socket, _format: Format) {
-        let _ = socket.send(Request::Action(Action::SwitchLayout { layout: LayoutSwitchTarget::Next }));
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        match &self.toggle_between {
+            Some((first, second)) => toggle_between(&niri, first, second),
+            None => {
+                niri.send(Request::Action(Action::SwitchLayout { layout: LayoutSwitchTarget::Next }));
+            }
+        }
+        if self.print && !config.quiet() {
+            let names = self.names.names(&config);
+            print_layout(&keyboard_layouts(&niri), &names, format, &config);
+        }
+    }
+}
+
+#[cfg(feature = "actions")]
+fn keyboard_layouts(niri: &Niri) -> KeyboardLayouts
+{
+    let Response::KeyboardLayouts(layouts) = niri.send(Request::KeyboardLayouts) else {
+        exit::fail(Code::Protocol, "Unexpected response to keyboard layouts request");
+    };
+    layouts
+}
+
+/// The line showing `layout` with `index` in `format`, without newline
+///
+/// The `layout` is the name to display, it is escaped according to
+/// `markup`. Returns [None] for CSV format, which has no layout line.
+pub fn render_layout(layout: &str, index: u8, format: &Format, markup: &waybar::Markup) -> Option<String>
+{
+    let text = markup.text(layout);
+    let mut out = Vec::new();
+    match format {
+        Format::Waybar | Format::Ironbar | Format::I3blocks | Format::I3blocksJson => {
+            let message = waybar::Message {
+                text,
+                class: vec!["layout".into()],
+                ..Default::default()
+            };
+            message.write_as(format, &mut out)
+        }
+        Format::Json | Format::Jsonl => serde_json::to_writer(&mut out, &serde_json::json!({ "layout": text, "idx": index })).unwrap(),
+        Format::Yambar => {
+            yambar::string(&mut out, "layout", &text);
+            yambar::int(&mut out, "index", index.into());
+        }
+        Format::Xmobar | Format::Lemonbar | Format::Status => return Some(markup.action(&text, "switch-layout")),
+        Format::Csv => return None,
+    }
+    // The writers of messages produce UTF-8 only
+    Some(String::from_utf8(out).unwrap())
+}
+
+/// Print current layout in `format` displayed according to `names`
+#[cfg(feature = "actions")]
+fn print_layout(layouts: &KeyboardLayouts, names: &LayoutNames, format: Format, config: &Config)
+{
+    let name = layouts.names.get(usize::from(layouts.current_idx)).map(|name| names.display(name));
+    let Some(line) = render_layout(name.as_deref().unwrap_or_default(), layouts.current_idx, &format, &config.markup(&format)) else {
+        exit::fail(Code::Usage, "The csv format has no layout line");
+    };
+    let mut out = line.into_bytes();
+    if format == Format::Jsonl {
+        jsonl::wrap("layout", &mut out);
+    }
+    out.push(b'\n');
+    exit::write_stdout(&out);
+}
+
+/// Switch to `second` layout if `first` is current and to `first` otherwise
+#[cfg(feature = "actions")]
+fn toggle_between(niri: &Niri, first: &str, second: &str)
+{
+    let layouts = keyboard_layouts(niri);
+    let registry = xkb::Registry::load();
+    let find = |layout: &str| layouts.names.iter().position(|name| matches_layout(&registry, name, layout))
+        .unwrap_or_else(|| exit::fail(Code::Usage, format!("No keyboard layout matches `{}`", layout)));
+    let (first, second) = (find(first), find(second));
+    let current = usize::from(layouts.current_idx);
+    let target = if current == first { second } else { first };
+    // niri can switch only to the neighbours, so take the shortest way
+    let count = layouts.names.len();
+    let forward = (target + count - current) % count;
+    let (layout, steps) = if forward <= count - forward {
+        (LayoutSwitchTarget::Next, forward)
+    } else {
+        (LayoutSwitchTarget::Prev, count - forward)
+    };
+    for _ in 0..steps {
+        niri.send(Request::Action(Action::SwitchLayout { layout }));
+    }
+}
+
+/// Whether layout `name` reported by niri is referred by user's `layout`
+#[cfg(feature = "actions")]
+fn matches_layout(registry: &xkb::Registry, name: &str, layout: &str) -> bool
+{
+    // Either exact code, or layout part of code of variant
+    if registry.description(layout) == Some(name) || registry.code(name).and_then(|code| code.split('(').next()) == Some(layout) {
+        return true;
+    }
+    let layout = layout.to_lowercase();
+    let lower = name.to_lowercase();
+    if lower == layout || lower.starts_with(&layout) {
+        return true;
+    }
+    match split_layout(&lower) {
+        Some((full, alias)) => full == layout || alias == Some(&layout),
+        None => false,
     }
 }
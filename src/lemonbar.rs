@@ -0,0 +1,35 @@
+//! The formatting of lemonbar texts.
+//!
+//! See the description of `%{...}` blocks in lemonbar(1). The commands of
+//! click areas are printed by lemonbar to its stdout, so it is expected to be
+//! piped to shell.
+
+/// Escape text to be placed within lemonbar formatting
+pub fn escape(text: &str) -> String {
+    text.replace('%', "%%")
+}
+
+/// Wrap text into block coloring it with `color`, like `#ff0000`
+///
+/// The text is left as is if color is empty.
+#[cfg(feature = "monitors")]
+pub fn color(text: &str, color: &str) -> String {
+    if color.is_empty() {
+        text.to_owned()
+    } else {
+        format!("%{{F{color}}}{text}%{{F-}}")
+    }
+}
+
+/// Wrap text into block drawing line under it
+#[cfg(feature = "monitors")]
+pub fn underline(text: &str) -> String {
+    format!("%{{+u}}{text}%{{-u}}")
+}
+
+/// Wrap text into click area running `niri-glue` with `args`
+#[cfg(any(feature = "monitors", feature = "actions"))]
+pub fn action(text: &str, args: &str) -> String {
+    let args = args.replace(':', "\\:");
+    format!("%{{A:niri-glue {args}:}}{text}%{{A}}")
+}
@@ -1,25 +1,142 @@
 //!
 //! Basic utility types. The [Args] is core type which both handles command line
-//! arguments and executes process. The main argument is
-//! [command](Args::command). The command specify which glue type this utility
-//! should provide. There could be optional [path](Args::path) argument which
-//! specifies path to niri socket and [format](Args::format) argument which
-//! specifies format of output messages (for each environment susbsystem could
-//! be different formats).
+//! arguments and executes process. The main argument is the [Command]. The
+//! command specify which glue type this utility should provide. There could be
+//! optional path to niri socket and [format](Format) of output messages (for
+//! each environment susbsystem could be different formats).
 //!
 //! Each [command's](Command) emum type implements [Parser] and [Runner] traits
 //! to parse arguments from one side and to perform action from another.
 //!
 #![warn(missing_docs)]
 
-use clap::Subcommand;
+use capabilities::Listing;
+use clap::{
+    builder::PossibleValuesParser, error::ErrorKind, ArgMatches,
+    CommandFactory, FromArgMatches, Subcommand,
+};
 pub use clap::{Parser, ValueEnum};
-use niri_ipc::socket::Socket;
-use std::path::PathBuf;
+use exit::{Code, Errors};
+use ipc::Socket;
+use niri_ipc::{socket, Reply, Request, Response};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
 
+#[cfg(feature = "actions")]
+mod actions;
+#[cfg(any(feature = "monitors", feature = "tui"))]
+mod apps;
+#[cfg(feature = "monitors")]
+mod bench;
+#[cfg(feature = "monitors")]
+mod cache;
+mod capabilities;
+mod config;
+pub mod exit;
+#[cfg(feature = "actions")]
+mod focus;
+#[cfg(feature = "monitors")]
+mod formatter;
+#[cfg(feature = "monitors")]
+mod generate;
+#[cfg(feature = "monitors")]
+mod group;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+mod i3blocks;
+#[cfg(feature = "monitors")]
+mod icons;
+#[cfg(feature = "actions")]
+mod install;
+mod ipc;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+mod jsonl;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+mod languages;
+#[cfg(any(feature = "monitors", feature = "actions"))]
 mod layout;
+mod lemonbar;
+mod module;
+#[cfg(feature = "monitors")]
+pub mod monitor;
+#[cfg(feature = "monitors")]
+mod multi;
+#[cfg(feature = "monitors")]
+mod output;
+#[cfg(feature = "monitors")]
+mod plugin;
+#[cfg(feature = "actions")]
+mod query;
+mod schema;
+#[cfg(any(feature = "monitors", feature = "tui"))]
+mod signals;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+mod sort;
+#[cfg(feature = "monitors")]
+mod status;
+#[cfg(feature = "tui")]
+mod switch;
+#[cfg(feature = "monitors")]
+pub mod testing;
+#[cfg(any(feature = "monitors", feature = "tui"))]
+mod text;
+#[cfg(feature = "monitors")]
+mod watch;
+pub mod waybar;
+#[cfg(feature = "monitors")]
+mod windows;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+mod workspaces;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+mod xkb;
+mod xmobar;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+mod yambar;
 
-pub use layout::{Layout, SwitchLayout};
+#[cfg(feature = "actions")]
+pub use actions::{
+    Batch, CenterColumn, CloseWindow, Column, ColumnWidth, FocusOutput,
+    MaximizeColumn, Monitors, MoveWindowToOutput, MoveWorkspaceToOutput, Quit,
+    Resize, ScreenTransition, SendAction, SendRequest, ShowHotkeyOverlay,
+    Spawn, ToggleFullscreen, ToggleOverview, ToggleTabbed,
+};
+#[cfg(feature = "monitors")]
+pub use bench::Bench;
+pub use config::{Config, ValidateConfig};
+#[cfg(feature = "actions")]
+pub use focus::RestoreFocus;
+#[cfg(feature = "monitors")]
+pub use generate::Generate;
+#[cfg(feature = "monitors")]
+pub use group::Group;
+#[cfg(feature = "actions")]
+pub use install::Install;
+#[cfg(feature = "actions")]
+pub use layout::SwitchLayout;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+pub use layout::{alias_layout, render_layout};
+#[cfg(any(feature = "monitors", feature = "actions"))]
+pub use layout::{Layout, LayoutNames};
+pub use module::{Module, Registry};
+#[cfg(feature = "monitors")]
+pub use multi::Multi;
+#[cfg(feature = "actions")]
+pub use query::Query;
+pub use schema::Schema;
+#[cfg(feature = "monitors")]
+pub use status::Status;
+#[cfg(feature = "tui")]
+pub use switch::Switch;
+#[cfg(feature = "monitors")]
+pub use windows::{FocusedWindow, Windows};
+#[cfg(feature = "actions")]
+pub use workspaces::{FocusWorkspace, MoveWindow, MoveWorkspace};
+#[cfg(feature = "monitors")]
+pub use workspaces::{FocusedWorkspace, Pager, Workspaces};
 
 /// Top-level arguments structure
 #[derive(Parser, Debug)]
@@ -32,15 +149,242 @@ pub use layout::{Layout, SwitchLayout};
 pub struct Args {
     /// The procedure to run
     #[command(subcommand)]
-    command: Command,
+    command: Option<Chosen>,
+
+    #[command(flatten)]
+    options: Options,
+
+    /// Print the names of output formats and exit
+    #[arg(long)]
+    list_formats: bool,
+
+    /// Print the names of commands and exit
+    #[arg(long)]
+    list_commands: bool,
+
+    /// Print the formats supported by each command and exit
+    #[arg(long)]
+    capabilities: bool,
+
+    /// Print the lists and capabilities as JSON
+    #[arg(long)]
+    json: bool,
+}
 
+/// The options common to all commands
+///
+/// The socket, configuration and format may also be set with
+/// `NIRI_GLUE_SOCKET`, `NIRI_GLUE_CONFIG` and `NIRI_GLUE_FORMAT` environment
+/// variables, or only for one command with ones like
+/// `NIRI_GLUE_SWITCH_LAYOUT_FORMAT`. The options take precedence over
+/// command variables, and those over common ones.
+#[derive(clap::Args, Debug)]
+struct Options {
     /// Optional path to niri socket
-    #[arg(short, long, help = "Path to niri socket")]
+    #[arg(short, long, help = "Path to niri socket [env: NIRI_GLUE_SOCKET]")]
     path: Option<PathBuf>,
 
-    /// The format of output messages
-    #[arg(short, long, default_value = "waybar")]
-    format: Format,
+    /// The form of failure reports on stderr
+    #[arg(long, value_enum, default_value = "human")]
+    errors: Errors,
+
+    /// Optional path to configuration file
+    #[arg(
+        short,
+        long,
+        help = "Path to configuration file [env: NIRI_GLUE_CONFIG]"
+    )]
+    config: Option<PathBuf>,
+
+    /// The format of output messages, waybar unless configured [env:
+    /// NIRI_GLUE_FORMAT]
+    #[arg(short, long)]
+    format: Option<Format>,
+
+    /// The profile of configuration file to apply [env: NIRI_GLUE_PROFILE]
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Escape markup characters in emitted texts
+    ///
+    /// Enabled by default for formats of bars with markup. In xmobar format
+    /// the texts with tags are wrapped into `<raw>` tag. Disable it to pass
+    /// markup from aliases and title rewrite rules to the bar intact.
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    escape_markup: Option<bool>,
+
+    /// Re-emit the current state of monitors every given count of seconds
+    ///
+    /// The state is re-emitted only if nothing was emitted during the
+    /// interval.
+    #[arg(long, value_name = "SECS")]
+    heartbeat: Option<u64>,
+
+    /// Poll niri every given count of milliseconds if it does not support
+    /// event stream
+    #[arg(long, value_name = "MS")]
+    poll_interval: Option<u64>,
+
+    /// Render monitor state only after no changes came during given count
+    /// of milliseconds
+    #[arg(long, value_name = "MS")]
+    debounce: Option<u64>,
+
+    /// Gather the state niri replays on connection during given count of
+    /// milliseconds and render it once, 50 by default
+    #[arg(long, value_name = "MS")]
+    settle: Option<u64>,
+
+    /// Flush stdout after every given count of lines of monitors
+    ///
+    /// By default each line is flushed at once. Greater values reduce
+    /// count of writes at the cost of delaying lines until enough of them
+    /// gathered.
+    #[arg(long, value_name = "LINES", value_parser = clap::value_parser!(u64).range(1..))]
+    flush_every: Option<u64>,
+
+    /// Print nothing on success of actions and skip their warnings
+    ///
+    /// The failures are still reported. Meant for actions run by keybinds
+    /// and click handlers of bars.
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Process only niri events of given type in monitors, may be repeated
+    ///
+    /// The rest of events are ignored. Keep the events with full state,
+    /// like `WindowsChanged`, for the monitors relying on them.
+    #[arg(
+        long = "event",
+        value_name = "TYPE",
+        value_parser = PossibleValuesParser::new(config::EVENTS)
+    )]
+    events: Vec<String>,
+
+    /// Print the actions as JSON requests to niri instead of sending them
+    ///
+    /// The queries needed to resolve the actions are still sent.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Fail if connection, request or read of event takes longer than given
+    /// count of milliseconds
+    ///
+    /// The process exits with code 7 on timeout. The monitors fail too if no
+    /// event comes in time, so choose the value accordingly for them.
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+
+    /// Retry connection to niri given count of times if its socket is
+    /// missing or refuses connections
+    ///
+    /// Applies to one-shot commands and to monitors, so they may be started
+    /// together with niri.
+    #[arg(long, value_name = "COUNT", default_value_t = 0)]
+    retries: u32,
+
+    /// Milliseconds to wait before the first retry, doubled for each next
+    /// one
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    retry_backoff: u64,
+
+    /// Pass the lines of monitors through the shell command
+    ///
+    /// The command gets on stdin JSON record per line with the type of
+    /// change and the state rendered in chosen format, and must print
+    /// single line in reply to each one. The printed lines are emitted
+    /// instead of rendered ones.
+    #[arg(long, value_name = "PROG")]
+    formatter_cmd: Option<String>,
+
+    /// Pass the lines of monitors through the native plugin
+    ///
+    /// The plugin is shared library exporting `niri_glue_format` function,
+    /// which gets the same records as `--formatter-cmd` program.
+    #[arg(long, value_name = "PATH", conflicts_with = "formatter_cmd")]
+    plugin: Option<PathBuf>,
+}
+
+impl Options {
+    /// Connect to niri and load configuration overridden by these options
+    ///
+    /// The unset options are taken from environment for `command`.
+    fn setup(self, command: &str) -> (Niri, Format, Config) {
+        let var = |name| var(command, name);
+        let config_path = self.config_path(command);
+        let path = self.path.or_else(|| var("SOCKET").map(PathBuf::from));
+        let niri = Niri {
+            path,
+            dry_run: self.dry_run,
+            timeout: self.timeout.map(Duration::from_millis),
+            retries: self.retries,
+            retry_backoff: Duration::from_millis(self.retry_backoff),
+        };
+        let mut overrides = Config::default();
+        overrides.escape_markup = self.escape_markup;
+        overrides.heartbeat = self.heartbeat;
+        overrides.poll_interval = self.poll_interval;
+        overrides.debounce = self.debounce;
+        overrides.settle = self.settle;
+        overrides.flush_every = self.flush_every;
+        overrides.quiet = Some(self.quiet).filter(|quiet| *quiet);
+        overrides.events = Some(self.events).filter(|e| !e.is_empty());
+        overrides.formatter_cmd = self.formatter_cmd;
+        overrides.plugin = self.plugin;
+        let profile = self.profile.or_else(|| var("PROFILE"));
+        let config =
+            Config::load(config_path.as_deref(), profile.as_deref(), overrides);
+        let format = match (self.format, var("FORMAT")) {
+            (Some(format), _) => format,
+            (None, Some(value)) => env_format(&value),
+            (None, None) => config.format.clone().unwrap_or(Format::Waybar),
+        };
+        (niri, format, config)
+    }
+
+    /// The path to configuration file, if set by option or environment
+    fn config_path(&self, command: &str) -> Option<PathBuf> {
+        let path = var(command, "CONFIG").map(PathBuf::from);
+        self.config.clone().or(path)
+    }
+}
+
+/// Whether connection failed due to niri not being ready yet
+fn transient(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+    )
+}
+
+/// The environment variable `name` for `command` or for all commands
+fn var(command: &str, name: &str) -> Option<String> {
+    let command = command.to_uppercase().replace('-', "_");
+    [
+        format!("NIRI_GLUE_{command}_{name}"),
+        format!("NIRI_GLUE_{name}"),
+    ]
+    .iter()
+    .filter_map(|var| env::var(var).ok())
+    .find(|value| !value.is_empty())
+}
+
+/// Parse format from environment variable or exit with usage error
+fn env_format(value: &str) -> Format {
+    Format::from_str(value, true).unwrap_or_else(|e| {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!("invalid format `{value}` in environment: {e}"),
+            )
+            .exit()
+    })
 }
 
 /// The list of supported commands
@@ -49,49 +393,654 @@ pub struct Args {
 pub enum Command {
     /// Check niri availability.
     ///
-    /// Exits with success if niri is available and panics if niri is
-    /// unavailable.
+    /// Exits with success if niri is available and with code 3 if niri is
+    /// unreachable.
     #[command(about, long_about)]
     Test(TestSocket),
 
+    /// Configuration checker.
+    ///
+    /// Loads configuration file with its includes and checks it along with
+    /// each of its host sections and profiles.
+    #[command(about, long_about)]
+    ValidateConfig(ValidateConfig),
+
+    /// JSON Schema printer.
+    ///
+    /// Prints JSON Schema of configuration file or of messages of JSON
+    /// based output formats, for editors and for validation by consumers.
+    #[command(about, long_about)]
+    Schema(Schema),
+
+    /// Bar configuration generator.
+    ///
+    /// Prints the snippet of bar configuration with modules running chosen
+    /// monitors.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Generate(Generate),
+
+    /// Session installer.
+    ///
+    /// Writes or removes systemd user unit running the focus keeper along
+    /// with niri session.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Install(Install),
+
     /// Keyboard layout monitor.
     ///
     /// Produces to stdout messages about keyboard layout actions.
+    #[cfg(feature = "monitors")]
     #[command(about, long_about)]
     Layout(Layout),
 
     /// Keyboard layout switcher.
     ///
     /// Switches the keyboard layout.
+    #[cfg(feature = "actions")]
     #[command(about, long_about)]
     SwitchLayout(SwitchLayout),
+
+    /// Interactive window switcher.
+    ///
+    /// Lists windows in terminal with fuzzy search and focuses the selected
+    /// one on Enter.
+    #[cfg(feature = "tui")]
+    #[command(about, long_about)]
+    Switch(Switch),
+
+    /// Workspace pager.
+    ///
+    /// Produces to stdout compact grid of workspaces of each output on every
+    /// change.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Pager(Pager),
+
+    /// Workspaces buttons monitor.
+    ///
+    /// Produces to stdout single waybar message with one Pango span per
+    /// workspace styled according to workspace state.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Workspaces(Workspaces),
+
+    /// Focused workspace monitor.
+    ///
+    /// Produces to stdout only the name or index of focused workspace and
+    /// its output on every change.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    FocusedWorkspace(FocusedWorkspace),
+
+    /// Workspace focus switcher.
+    ///
+    /// Focuses the workspace by index or name, or relative to the current
+    /// one.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    FocusWorkspace(FocusWorkspace),
+
+    /// Window mover.
+    ///
+    /// Moves the focused window or the window with given id to the workspace
+    /// by index or name, or relative to the current one.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    MoveWindow(MoveWindow),
+
+    /// Workspace reorderer.
+    ///
+    /// Moves the focused workspace up or down, or any workspace to given
+    /// index on its output.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    MoveWorkspace(MoveWorkspace),
+
+    /// Window mover between outputs.
+    ///
+    /// Moves the focused window or the window with given id to the active
+    /// workspace of another output.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    MoveWindowToOutput(MoveWindowToOutput),
+
+    /// Output focus switcher.
+    ///
+    /// Focuses another output by name or relative to the focused one.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    FocusOutput(FocusOutput),
+
+    /// Workspace mover between outputs.
+    ///
+    /// Moves the focused workspace to another output, optionally along with
+    /// the focus.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    MoveWorkspaceToOutput(MoveWorkspaceToOutput),
+
+    /// Focus keeper.
+    ///
+    /// Focuses the last focused window of workspace again when the workspace
+    /// is activated.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    RestoreFocus(RestoreFocus),
+
+    /// Column builder.
+    ///
+    /// Consumes the window to the right into the column or expels the window
+    /// from its column.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Column(Column),
+
+    /// Tabbed column toggle.
+    ///
+    /// Switches the focused column between normal and tabbed display.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    ToggleTabbed(ToggleTabbed),
+
+    /// Column width switcher.
+    ///
+    /// Changes the width of the focused column and prints the applied
+    /// change.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    ColumnWidth(ColumnWidth),
+
+    /// Hold-to-resize helper.
+    ///
+    /// Keeps changing the size of the focused column and window by small
+    /// steps until killed.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Resize(Resize),
+
+    /// Column maximizer.
+    ///
+    /// Maximizes the focused column or restores its width with `--toggle`.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    MaximizeColumn(MaximizeColumn),
+
+    /// Column centering.
+    ///
+    /// Centers the focused column on the screen.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    CenterColumn(CenterColumn),
+
+    /// Windows monitor.
+    ///
+    /// Produces to stdout the list of windows with their application icons
+    /// on every change.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Windows(Windows),
+
+    /// Focused window monitor.
+    ///
+    /// Produces to stdout the title of focused window on every change.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Window(FocusedWindow),
+
+    /// Status line monitor.
+    ///
+    /// Produces to stdout single line combining keyboard layout, focused
+    /// workspace and title of focused window on every change.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Status(Status),
+
+    /// Combined monitors.
+    ///
+    /// Produces to stdout single waybar message joining texts and classes of
+    /// several monitors on every change.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Group(Group),
+
+    /// Multiplexed monitors.
+    ///
+    /// Produces to stdout lines of several monitors tagged by monitor name
+    /// over single event stream.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about)]
+    Multi(Multi),
+
+    /// Window fullscreen toggle.
+    ///
+    /// Toggles fullscreen of the focused window or of the window with given
+    /// id.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    ToggleFullscreen(ToggleFullscreen),
+
+    /// Window closer.
+    ///
+    /// Closes the focused window or the window with given id.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    CloseWindow(CloseWindow),
+
+    /// Niri exit.
+    ///
+    /// Asks for confirmation and exits niri, optionally closing all windows
+    /// first.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Quit(Quit),
+
+    /// Monitors power switch.
+    ///
+    /// Powers monitors off or on, or toggles their power.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Monitors(Monitors),
+
+    /// Hotkey overlay.
+    ///
+    /// Shows niri's overlay of hotkeys.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    ShowHotkeyOverlay(ShowHotkeyOverlay),
+
+    /// Workspaces overview toggle.
+    ///
+    /// Toggles, opens or closes niri's overview of workspaces.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    ToggleOverview(ToggleOverview),
+
+    /// Screen transition.
+    ///
+    /// Freezes the screen and crossfades to its new contents.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    ScreenTransition(ScreenTransition),
+
+    /// Program launcher.
+    ///
+    /// Spawns program by niri with optional environment and working
+    /// directory.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Spawn(Spawn),
+
+    /// Arbitrary action.
+    ///
+    /// Sends any niri action given as JSON or as name with fields.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Action(SendAction),
+
+    /// Niri state query.
+    ///
+    /// Prints the queried part of niri state in chosen format, once or on
+    /// every change.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Query(Query),
+
+    /// Arbitrary request.
+    ///
+    /// Sends any niri request and prints the reply in chosen format.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Request(SendRequest),
+
+    /// Batch of actions.
+    ///
+    /// Reads actions from stdin one per line and sends them in order.
+    #[cfg(feature = "actions")]
+    #[command(about, long_about)]
+    Batch(Batch),
+
+    /// Event throughput benchmark.
+    ///
+    /// Pushes synthetic event stream through the chosen monitor without
+    /// connecting to niri and reports the rate of processed events.
+    #[cfg(feature = "monitors")]
+    #[command(about, long_about, hide = true)]
+    Bench(Bench),
+}
+
+/// The command with its name on command line, as clap parsed it
+#[derive(Debug, Clone)]
+struct Chosen {
+    name: String,
+    command: Command,
+}
+
+impl FromArgMatches for Chosen {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        let name = matches.subcommand_name().unwrap_or_default().to_owned();
+        let command = Command::from_arg_matches(matches)?;
+        Ok(Self { name, command })
+    }
+
+    fn update_from_arg_matches(
+        &mut self,
+        matches: &ArgMatches,
+    ) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+impl Subcommand for Chosen {
+    fn augment_subcommands(command: clap::Command) -> clap::Command {
+        Command::augment_subcommands(command)
+    }
+
+    fn augment_subcommands_for_update(command: clap::Command) -> clap::Command {
+        Command::augment_subcommands_for_update(command)
+    }
+
+    fn has_subcommand(name: &str) -> bool {
+        Command::has_subcommand(name)
+    }
 }
 
 /// The list of available formats of output messages
-#[derive(ValueEnum, Debug, Clone, PartialEq)]
+#[derive(ValueEnum, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
 pub enum Format {
     /// The waybar custom module format (See https://github.com/Alexays/Waybar)
     Waybar,
+    /// The plain JSON value per line
+    Json,
+    /// The JSON value per line wrapped into record with timestamp and type,
+    /// for logging rather than bars
+    Jsonl,
+    /// The comma separated values with header, for `query` and `request`
+    /// only
+    Csv,
+    /// The yambar script module format (See
+    /// https://codeberg.org/dnkl/yambar)
+    Yambar,
+    /// The xmobar markup text per line, for `CommandReader` or `PipeReader`
+    /// plugins (See https://codeberg.org/xmobar/xmobar)
+    Xmobar,
+    /// The lemonbar formatting text per line (See
+    /// https://github.com/LemonBoy/bar), pipe lemonbar output to shell to
+    /// run the click commands
+    Lemonbar,
+    /// The Pango markup text per line, for ironbar script modules in watch
+    /// mode (See https://github.com/JakeStanger/ironbar)
+    Ironbar,
+    /// The i3blocks lines of full text, short text and color (See
+    /// https://github.com/vivien/i3blocks)
+    I3blocks,
+    /// The i3blocks JSON object per line, for persistent blocks with
+    /// `format=json`
+    I3blocksJson,
+    /// The plain text per line, for status bars reading stdin like dwl's
+    /// one, see `status` command
+    Status,
+}
+
+impl Format {
+    /// Whether each line of the format is JSON value
+    pub fn is_json(&self) -> bool {
+        matches!(
+            self,
+            Self::Waybar | Self::Json | Self::Jsonl | Self::I3blocksJson
+        )
+    }
 }
 
 /// The trait for subcommand
 pub trait Runner {
-    /// The [Args] will create socket for niri and pass it here
-    fn run(self, socket: Socket, format: Format);
+    /// The [Args] will create connection to niri, load configuration and
+    /// pass them here
+    fn run(self, niri: Niri, format: Format, config: Config);
+}
+
+/// The connection to niri socket
+///
+/// Each request to niri requires its own socket, so the commands which need
+/// several requests may [connect](Niri::connect) as many times as they want.
+#[derive(Debug, Clone)]
+pub struct Niri {
+    path: Option<PathBuf>,
+    dry_run: bool,
+    timeout: Option<Duration>,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl Niri {
+    /// Open new socket to niri
+    ///
+    /// The connection is retried while socket is missing or refuses
+    /// connections, as it does during startup of niri. Exits the process
+    /// with code 3 if niri is unreachable and with 7 on timeout.
+    pub fn connect(&self) -> Socket {
+        let path = self.path.clone().or_else(|| {
+            env::var_os(socket::SOCKET_PATH_ENV).map(PathBuf::from)
+        });
+        let Some(path) = path else {
+            exit::fail(
+                Code::Unreachable,
+                format!(
+                    "{} is not set, are you running this within niri?",
+                    socket::SOCKET_PATH_ENV
+                ),
+            )
+        };
+        let mut delay = self.retry_backoff;
+        for _ in 0..self.retries {
+            match Socket::connect_to(&path, self.timeout) {
+                Err(e) if transient(&e) => thread::sleep(delay),
+                result => return Niri::connected(&path, result),
+            }
+            delay = delay.saturating_mul(2);
+        }
+        Niri::connected(&path, Socket::connect_to(&path, self.timeout))
+    }
+
+    fn connected(path: &Path, result: io::Result<Socket>) -> Socket {
+        result.unwrap_or_else(|e| {
+            exit::fail(
+                Code::of(&e).unwrap_or(Code::Unreachable),
+                format!("Failed to connect to niri at {}: {e}", path.display()),
+            )
+        })
+    }
+
+    /// Send single request to niri over new socket and return the response
+    ///
+    /// Exits the process with code 4 on failure of connection, with 5 if
+    /// niri rejects request and with 7 on timeout.
+    pub fn send(&self, request: Request) -> Response {
+        let name = serde_json::to_string(&request).unwrap_or_default();
+        match self.request(request) {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => exit::fail(
+                Code::Rejected,
+                format!("Niri rejected request {name}: {e}"),
+            ),
+            Err(e) => exit::fail(
+                Code::of(&e).unwrap_or(Code::Protocol),
+                format!("Failed to send request {name}: {e}"),
+            ),
+        }
+    }
+
+    /// Send single request to niri over new socket and return the reply
+    ///
+    /// In dry-run mode the actions are printed to stdout instead and
+    /// reported as handled.
+    pub fn request(&self, request: Request) -> io::Result<Reply> {
+        let action = matches!(request, Request::Action(_));
+        self.exchange(request, action)
+    }
+
+    /// Send action missing in `niri_ipc` to niri, exiting on failure
+    ///
+    /// Exits the process with the same codes as [send](Niri::send), the
+    /// older niri rejects such actions.
+    #[cfg(feature = "actions")]
+    pub(crate) fn send_action(&self, action: ipc::Action) {
+        let request = ipc::Request::Action(action);
+        let name = serde_json::to_string(&request).unwrap_or_default();
+        match self.exchange(request, true) {
+            Ok(Ok(_)) => (),
+            Ok(Err(e)) => exit::fail(
+                Code::Rejected,
+                format!("Niri rejected request {name}: {e}"),
+            ),
+            Err(e) => exit::fail(
+                Code::of(&e).unwrap_or(Code::Protocol),
+                format!("Failed to send request {name}: {e}"),
+            ),
+        }
+    }
+
+    /// Send `request` unless it is `action` in dry-run mode
+    fn exchange(
+        &self,
+        request: impl Serialize,
+        action: bool,
+    ) -> io::Result<Reply> {
+        if self.dry_run && action {
+            let mut line = serde_json::to_vec(&request)?;
+            line.push(b'\n');
+            exit::write_stdout(&line);
+            return Ok(Ok(Response::Handled));
+        }
+        self.connect().send(request).map(|(reply, _)| reply)
+    }
+
+    /// Whether actions are printed instead of sending
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
 }
 
 impl Args {
     /// Run chosen subcommand
     pub fn run(self) {
-        let socket = if let Some(path) = self.path {
-            Socket::connect_to(path).unwrap()
+        exit::report_as(self.options.errors);
+        let listing = if self.list_formats {
+            Some(Listing::Formats)
+        } else if self.list_commands {
+            Some(Listing::Commands)
+        } else if self.capabilities {
+            Some(Listing::Capabilities)
         } else {
-            Socket::connect().unwrap()
+            None
+        };
+        if let Some(listing) = listing {
+            return capabilities::print(&Self::command(), listing, self.json);
+        }
+        let Some(Chosen { name, command }) = self.command else {
+            Self::command()
+                .error(ErrorKind::MissingSubcommand, "the command is required")
+                .exit();
         };
-        match self.command {
-            Command::Layout(cmd) => cmd.run(socket, self.format),
-            Command::SwitchLayout(cmd) => cmd.run(socket, self.format),
-            Command::Test(cmd) => cmd.run(socket, self.format),
+        if let Command::ValidateConfig(cmd) = command {
+            let path = self.options.config_path("validate-config");
+            return cmd.run(path.as_deref(), self.options.quiet);
+        }
+        let (niri, format, config) = self.options.setup(&name);
+        if !capabilities::supports(&name, &format) {
+            let format = capabilities::name(&format);
+            exit::fail(
+                Code::Usage,
+                format!("The {format} format is not supported by {name}"),
+            );
+        }
+        match command {
+            #[cfg(feature = "monitors")]
+            Command::Layout(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::SwitchLayout(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "tui")]
+            Command::Switch(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Pager(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Workspaces(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::FocusedWorkspace(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::FocusWorkspace(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::MoveWindow(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::MoveWorkspace(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::MoveWindowToOutput(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::FocusOutput(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::MoveWorkspaceToOutput(cmd) => {
+                cmd.run(niri, format, config)
+            }
+            #[cfg(feature = "actions")]
+            Command::RestoreFocus(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Column(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::ToggleTabbed(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::ColumnWidth(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Resize(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::MaximizeColumn(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::CenterColumn(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Windows(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Window(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Status(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Group(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Multi(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::ToggleFullscreen(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::CloseWindow(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Quit(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Monitors(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::ShowHotkeyOverlay(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::ToggleOverview(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::ScreenTransition(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Spawn(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Action(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Query(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Request(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Batch(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Bench(cmd) => cmd.run(niri, format, config),
+            Command::Test(cmd) => cmd.run(niri, format, config),
+            Command::Schema(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "monitors")]
+            Command::Generate(cmd) => cmd.run(niri, format, config),
+            #[cfg(feature = "actions")]
+            Command::Install(cmd) => cmd.run(niri, format, config),
+            Command::ValidateConfig(_) => unreachable!(),
         }
     }
 }
@@ -101,5 +1050,7 @@ impl Args {
 pub struct TestSocket {}
 
 impl Runner for TestSocket {
-    fn run(self, _socket: Socket, _format: Format) {}
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        niri.connect();
+    }
 }
@@ -0,0 +1,98 @@
+//! The commands provided by other crates.
+//!
+//! The crate which needs its own commands along with built-in ones
+//! implements [Module] for each of them and runs the [Registry] of them
+//! instead of [Args] in its `main`:
+//!
+//! ```no_run
+//! use niri_glue::{Config, Format, Module, Niri, Registry};
+//!
+//! struct Hello;
+//!
+//! impl Module for Hello {
+//!     fn command(&self) -> clap::Command {
+//!         clap::Command::new("hello").about("Greeter")
+//!     }
+//!
+//!     fn run(&self, _: &clap::ArgMatches, _: Niri, _: Format, _: Config) {
+//!         println!("Hello");
+//!     }
+//! }
+//!
+//! Registry::new().register(Hello).run();
+//! ```
+//!
+//! Modules get the same connection to niri, format and configuration as
+//! built-in commands. The long-running ones may implement
+//! [Monitor](crate::monitor::Monitor) and pass it to
+//! [run](crate::monitor::run) to get event stream handling, resynchronization
+//! and output of built-in monitors.
+
+use crate::{exit, Args, Config, Format, Niri, Options};
+
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+
+/// The command plugged into command line of niri-glue
+pub trait Module {
+    /// The subcommand parsing arguments of module
+    ///
+    /// The module is run by name of subcommand, which must differ from
+    /// names of built-in commands and other modules.
+    fn command(&self) -> clap::Command;
+
+    /// Run module with arguments parsed by its [command](Module::command)
+    fn run(
+        &self,
+        matches: &ArgMatches,
+        niri: Niri,
+        format: Format,
+        config: Config,
+    );
+}
+
+/// The modules run in addition to built-in commands
+#[derive(Default)]
+pub struct Registry {
+    modules: Vec<Box<dyn Module>>,
+}
+
+impl Registry {
+    /// The registry without modules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add module to the registry
+    pub fn register(mut self, module: impl Module + 'static) -> Self {
+        self.modules.push(Box::new(module));
+        self
+    }
+
+    /// Parse arguments of process and run chosen command or module
+    pub fn run(self) {
+        let mut command = Args::command();
+        for module in &self.modules {
+            command = command.subcommand(module.command());
+        }
+        let matches = command.get_matches();
+        let chosen = matches.subcommand().and_then(|(name, matches)| {
+            let module = self
+                .modules
+                .iter()
+                .find(|module| module.command().get_name() == name)?;
+            Some((module, matches))
+        });
+        if let Some((module, sub)) = chosen {
+            let options = Options::from_arg_matches(&matches)
+                .unwrap_or_else(|e| e.exit());
+            exit::report_as(options.errors);
+            let name = module.command().get_name().to_owned();
+            let (niri, format, config) = options.setup(&name);
+            module.run(sub, niri, format, config);
+        } else {
+            Args::from_arg_matches(&matches)
+                .unwrap_or_else(|e| e.exit())
+                .run();
+        }
+    }
+}
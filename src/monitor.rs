@@ -0,0 +1,456 @@
+//! The long-running commands rendering niri state on every change.
+
+use crate::{
+    cache::Cache,
+    exit::{self, Code},
+    formatter::{Formatter, Program},
+    output::Output,
+    plugin::Plugin,
+    signals::{self, Signal},
+    watch::Watcher,
+    Config, Format, Niri,
+};
+
+use niri_ipc::{
+    state::{WindowsState, WorkspacesState},
+    Event, KeyboardLayouts, Request, Response,
+};
+use std::{
+    io, mem,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The default interval of polling when event stream is unavailable
+const DEFAULT_POLL_INTERVAL: u64 = 1000;
+
+/// The default time in milliseconds for niri to replay its state on connect
+const DEFAULT_SETTLE: u64 = 50;
+
+/// The query of niri state used when event stream is unavailable or the
+/// tracked state is out of sync
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Query {
+    /// The keyboard layouts and the current one
+    KeyboardLayouts,
+    /// The workspaces of all outputs
+    Workspaces,
+    /// The open windows
+    Windows,
+}
+
+impl Query {
+    fn request(&self) -> Request {
+        match self {
+            Self::KeyboardLayouts => Request::KeyboardLayouts,
+            Self::Workspaces => Request::Workspaces,
+            Self::Windows => Request::Windows,
+        }
+    }
+
+    /// The event replacing the whole state part with queried one
+    fn event(&self, response: Response) -> Option<Event> {
+        match response {
+            Response::KeyboardLayouts(keyboard_layouts) => {
+                Some(Event::KeyboardLayoutsChanged { keyboard_layouts })
+            }
+            Response::Workspaces(workspaces) => {
+                Some(Event::WorkspacesChanged { workspaces })
+            }
+            Response::Windows(windows) => {
+                Some(Event::WindowsChanged { windows })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The message delivered to monitor event loop
+enum Message {
+    Event(io::Result<Event>),
+    Signal(Signal),
+    /// The configuration file changed
+    Reload,
+}
+
+/// The long-running command rendering the state tracked from event stream
+pub trait Monitor {
+    /// Apply event to the tracked state
+    ///
+    /// Returns whether the state could have been changed by event.
+    fn apply(&mut self, event: Event) -> bool;
+
+    /// Whether event refers only to things known in the tracked state
+    ///
+    /// Inconsistent events are not applied, the whole state is queried from
+    /// niri instead.
+    fn consistent(&self, event: &Event) -> bool;
+
+    /// Render the current state to the line of output appended to `out`
+    ///
+    /// Returns `false` if there is nothing to show yet.
+    fn render(&mut self, out: &mut Vec<u8>) -> bool;
+
+    /// The queries to poll if event stream is not supported by niri
+    fn queries(&self) -> Vec<Query>;
+
+    /// Apply reloaded configuration
+    fn reload(&mut self, config: &Config);
+}
+
+impl<M: Monitor + ?Sized> Monitor for Box<M> {
+    fn apply(&mut self, event: Event) -> bool {
+        (**self).apply(event)
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        (**self).consistent(event)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        (**self).render(out)
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        (**self).queries()
+    }
+
+    fn reload(&mut self, config: &Config) {
+        (**self).reload(config)
+    }
+}
+
+/// Run monitor over niri event stream until the stream fails
+///
+/// The line is printed only when it differs from previously printed one,
+/// unless the heartbeat interval elapsed since the last print. With debounce
+/// the state is rendered only after no changes came during debounce
+/// interval. The state niri replays on connect is rendered once after the
+/// settle interval since its first event. Printing never blocks processing
+/// of events.
+///
+/// The line printed by previous run with the same arguments is printed at
+/// once before connecting to niri, so consumers have something to show while
+/// niri is starting.
+///
+/// On `SIGUSR1` the last printed line is printed again. On `SIGHUP` or change
+/// of configuration file or of files it includes the configuration is
+/// reloaded and the state is rendered again.
+///
+/// In [Format::Jsonl] each line is wrapped into record with the type of
+/// niri event which caused it, and no cached line is printed. The lines are
+/// embedded into records as is in [JSON](Format::is_json) formats and as
+/// strings in others.
+pub fn run(monitor: impl Monitor, niri: &Niri, format: Format, config: Config) {
+    let json = format.is_json();
+    serve(monitor, niri, format, config, json)
+}
+
+/// Run monitor, whose lines are text in any format, like [run]
+pub(crate) fn run_text(
+    monitor: impl Monitor,
+    niri: &Niri,
+    format: Format,
+    config: Config,
+) {
+    serve(monitor, niri, format, config, false)
+}
+
+fn serve(
+    mut monitor: impl Monitor,
+    niri: &Niri,
+    format: Format,
+    mut config: Config,
+    json: bool,
+) {
+    let jsonl = format == Format::Jsonl;
+    let formatter = formatter(&config);
+    // Whether lines are wrapped into records with the type of change
+    let records = jsonl || formatter.is_some();
+    // The cached line is meant for bars, logs get only the actual records
+    let cache = if jsonl { None } else { Cache::new() };
+    // The last printed line, empty if nothing was printed yet
+    let mut last = cache.as_ref().and_then(Cache::read).unwrap_or_default();
+    let mut line = Vec::new();
+    let flush_every = config.flush_every.unwrap_or(1);
+    let output = Output::new(cache, flush_every, jsonl, json, formatter);
+    if !last.is_empty() {
+        // Emitted at once, as connection to niri may fail
+        output.emit(&last, "Cached");
+    }
+    let poll_interval = config.poll_interval.unwrap_or(DEFAULT_POLL_INTERVAL);
+    let interval = Duration::from_millis(poll_interval);
+    let (tx, events) = mpsc::channel();
+    let signal_tx = tx.clone();
+    let watched = signals::watch(&[Signal::Usr1, Signal::Hup], move |signal| {
+        let _ = signal_tx.send(Message::Signal(signal));
+    });
+    // Without signals the configuration is still reloaded on its change
+    if let Err(e) = watched {
+        eprintln!("Failed to watch signals: {e}");
+    }
+    let reload_tx = tx.clone();
+    let watcher = Watcher::new(move || {
+        let _ = reload_tx.send(Message::Reload);
+    });
+    // Without inotify the configuration is still reloaded on SIGHUP
+    let watcher = watcher.ok();
+    if let Some(watcher) = &watcher {
+        watcher.set(config.sources());
+    }
+    subscribe(niri, monitor.queries(), interval, tx);
+    let mut heartbeat = config.heartbeat.map(Duration::from_secs);
+    let mut debounce = Duration::from_millis(config.debounce.unwrap_or(0));
+    let settle = config.settle.unwrap_or(DEFAULT_SETTLE);
+    let settle = Duration::from_millis(settle);
+    let mut printed = Instant::now();
+    // The moment to render the pending changes at
+    let mut settled: Option<Instant> = None;
+    // The end of initial burst of events, known since its first event
+    let mut burst: Option<Instant> = None;
+    // The type of the last change for JSON lines records
+    let mut cause = "";
+    loop {
+        let deadline = [settled, heartbeat.map(|hb| printed + hb)]
+            .into_iter()
+            .flatten()
+            .min();
+        let message = match deadline {
+            Some(deadline) => {
+                let timeout =
+                    deadline.saturating_duration_since(Instant::now());
+                match events.recv_timeout(timeout) {
+                    Ok(message) => Some(message),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        exit::fail(Code::Protocol, "Event stream closed")
+                    }
+                }
+            }
+            None => Some(events.recv().unwrap_or_else(|_| {
+                exit::fail(Code::Protocol, "Event stream closed")
+            })),
+        };
+        match message {
+            Some(Message::Event(event)) => {
+                let event = event.unwrap_or_else(|e| {
+                    exit::fail(
+                        Code::of(&e).unwrap_or(Code::Protocol),
+                        format!("Failed to read event stream: {e}"),
+                    )
+                });
+                let (changed, kind) = if !config.subscribed(event_type(&event))
+                {
+                    (false, "")
+                } else if monitor.consistent(&event) {
+                    let kind = if records { event_type(&event) } else { "" };
+                    (monitor.apply(event), kind)
+                } else {
+                    (resync(&mut monitor, niri), "Resync")
+                };
+                if changed {
+                    let now = Instant::now();
+                    let burst = *burst.get_or_insert(now + settle);
+                    cause = kind;
+                    settled = Some((now + debounce).max(burst));
+                }
+            }
+            Some(Message::Signal(Signal::Usr1)) if !last.is_empty() => {
+                output.emit(&last, "Refresh");
+                printed = Instant::now();
+            }
+            Some(Message::Signal(Signal::Hup) | Message::Reload) => {
+                match config.reload() {
+                    Ok(reloaded) => {
+                        config = reloaded;
+                        heartbeat = config.heartbeat.map(Duration::from_secs);
+                        debounce =
+                            Duration::from_millis(config.debounce.unwrap_or(0));
+                        monitor.reload(&config);
+                        settled = Some(Instant::now());
+                        cause = "Reload";
+                    }
+                    Err(e) => eprintln!("Failed to reload configuration: {e}"),
+                }
+                // The includes may have changed, even if they are broken
+                if let Some(watcher) = &watcher {
+                    watcher.set(config.sources());
+                }
+            }
+            Some(Message::Signal(Signal::Usr1 | Signal::Winch)) | None => (),
+        }
+        let now = Instant::now();
+        if settled.is_some_and(|settled| settled <= now) {
+            settled = None;
+            line.clear();
+            if monitor.render(&mut line) && line != last {
+                output.emit(&line, cause);
+                mem::swap(&mut line, &mut last);
+                printed = now;
+            }
+        } else if heartbeat.is_some_and(|hb| printed + hb <= now) {
+            if !last.is_empty() {
+                output.emit(&last, "Heartbeat");
+            }
+            printed = now;
+        }
+    }
+}
+
+/// The formatter chosen by configuration, if any
+fn formatter(config: &Config) -> Option<Formatter> {
+    if let Some(path) = &config.plugin {
+        let plugin = Plugin::load(path).unwrap_or_else(|e| {
+            let message =
+                format!("Failed to load plugin {}: {e}", path.display());
+            exit::fail(Code::Usage, message)
+        });
+        return Some(Formatter::Plugin(plugin));
+    }
+    let cmd = config.formatter_cmd.as_deref()?;
+    let program = Program::spawn(cmd).unwrap_or_else(|e| {
+        exit::fail(Code::Usage, format!("Failed to run formatter: {e}"))
+    });
+    Some(Formatter::Program(program))
+}
+
+/// Replace the tracked state with the one queried from niri
+///
+/// Returns whether any of queries succeeded.
+fn resync(monitor: &mut impl Monitor, niri: &Niri) -> bool {
+    let mut changed = false;
+    for query in monitor.queries() {
+        let response = match niri.connect().send(query.request()) {
+            Ok((Ok(response), _)) => response,
+            Ok((Err(e), _)) => {
+                eprintln!("Failed to resynchronize state: {e}");
+                continue;
+            }
+            Err(e) => {
+                eprintln!("Failed to resynchronize state: {e}");
+                continue;
+            }
+        };
+        if let Some(event) = query.event(response) {
+            changed |= monitor.apply(event);
+        }
+    }
+    changed
+}
+
+/// The name of event variant, like `WindowFocusChanged`
+fn event_type(event: &Event) -> &'static str {
+    match event {
+        Event::WorkspacesChanged { .. } => "WorkspacesChanged",
+        Event::WorkspaceActivated { .. } => "WorkspaceActivated",
+        Event::WorkspaceActiveWindowChanged { .. } => {
+            "WorkspaceActiveWindowChanged"
+        }
+        Event::WindowsChanged { .. } => "WindowsChanged",
+        Event::WindowOpenedOrChanged { .. } => "WindowOpenedOrChanged",
+        Event::WindowClosed { .. } => "WindowClosed",
+        Event::WindowFocusChanged { .. } => "WindowFocusChanged",
+        Event::KeyboardLayoutsChanged { .. } => "KeyboardLayoutsChanged",
+        Event::KeyboardLayoutSwitched { .. } => "KeyboardLayoutSwitched",
+    }
+}
+
+/// Whether event refers only to known workspaces
+pub fn known_workspaces(state: &WorkspacesState, event: &Event) -> bool {
+    match event {
+        Event::WorkspaceActivated { id, .. } => {
+            state.workspaces.contains_key(id)
+        }
+        Event::WorkspaceActiveWindowChanged { workspace_id, .. } => {
+            state.workspaces.contains_key(workspace_id)
+        }
+        _ => true,
+    }
+}
+
+/// Whether event refers only to known windows
+pub fn known_windows(state: &WindowsState, event: &Event) -> bool {
+    match event {
+        Event::WindowClosed { id } => state.windows.contains_key(id),
+        Event::WindowFocusChanged { id: Some(id) } => {
+            state.windows.contains_key(id)
+        }
+        _ => true,
+    }
+}
+
+/// Whether event refers only to known keyboard layouts
+pub fn known_layouts(layouts: Option<&KeyboardLayouts>, event: &Event) -> bool {
+    match event {
+        Event::KeyboardLayoutSwitched { idx } => {
+            layouts.is_some_and(|kb| usize::from(*idx) < kb.names.len())
+        }
+        _ => true,
+    }
+}
+
+/// Request event stream and read it in background thread
+///
+/// If niri rejects event stream request, the `queries` are sent every
+/// `interval` instead.
+fn subscribe(
+    niri: &Niri,
+    queries: Vec<Query>,
+    interval: Duration,
+    tx: Sender<Message>,
+) {
+    let socket = niri.connect();
+    let niri = niri.clone();
+    thread::spawn(move || {
+        let mut functor = match socket.send(Request::EventStream) {
+            Ok((Ok(_), functor)) => functor,
+            Ok((Err(_), _)) => return poll(&niri, &queries, interval, tx),
+            Err(e) => {
+                let _ = tx.send(Message::Event(Err(e)));
+                return;
+            }
+        };
+        loop {
+            let event = functor();
+            let failed = event.is_err();
+            if tx.send(Message::Event(event)).is_err() || failed {
+                break;
+            }
+        }
+    });
+}
+
+/// Send queries periodically and produce events for changed responses
+fn poll(
+    niri: &Niri,
+    queries: &[Query],
+    interval: Duration,
+    tx: Sender<Message>,
+) {
+    let mut last = vec![None; queries.len()];
+    loop {
+        for (query, last) in queries.iter().zip(last.iter_mut()) {
+            let response = match niri.connect().send(query.request()) {
+                Ok((Ok(response), _)) => response,
+                // The query is not supported by this niri either
+                Ok((Err(_), _)) => continue,
+                Err(e) => {
+                    let _ = tx.send(Message::Event(Err(e)));
+                    return;
+                }
+            };
+            let Some(event) = query.event(response) else {
+                continue;
+            };
+            let serialized = serde_json::to_string(&event).ok();
+            if *last != serialized {
+                *last = serialized;
+                if tx.send(Message::Event(Ok(event))).is_err() {
+                    return;
+                }
+            }
+        }
+        thread::sleep(interval);
+    }
+}
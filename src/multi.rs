@@ -0,0 +1,162 @@
+use crate::{
+    capabilities,
+    exit::{self, Code},
+    group::{Builtin, Modules},
+    monitor::{self, Monitor, Query},
+    Config, Format, Niri, Runner,
+};
+
+use clap::{Parser, ValueEnum};
+use niri_ipc::Event;
+use std::io::{self, Error};
+
+/// Multiplexed monitors.
+///
+/// Produces to stdout lines of several monitors over single event stream.
+/// Each line is tagged by name of its monitor, like `layout {"text":"US"}`.
+/// The lines of all monitors are printed together on every change, so the
+/// state is complete even if some of changes were skipped by slow consumer.
+/// The monitors use their default options and aliases from configuration
+/// file.
+///
+/// Each monitor prints its lines in the global format unless it is given
+/// its own one by `--module` option.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Multi {
+    /// The monitors to run
+    ///
+    /// The `layout`, `workspaces` and `window` ones are run if no monitors
+    /// are given here or by `--module` options.
+    #[arg(value_enum)]
+    modules: Vec<Builtin>,
+
+    /// The monitor to run with its own format
+    ///
+    /// Each argument must have format `MONITOR=FORMAT`, like
+    /// `windows=json`. The monitor is added if it is not given already.
+    #[arg(
+        short = 'm',
+        long = "module",
+        value_parser = parse_module,
+        number_of_values = 1,
+        value_name = "MONITOR=FORMAT"
+    )]
+    formats: Vec<(Builtin, Format)>,
+}
+
+fn parse_module(s: &str) -> Result<(Builtin, Format), Error> {
+    let invalid = |e| Error::new(io::ErrorKind::InvalidInput, e);
+    let Some((module, format)) = s.split_once('=') else {
+        return Err(invalid(format!("no `=` found in `{s}`")));
+    };
+    let module = Builtin::from_str(module, false).map_err(invalid)?;
+    let format = Format::from_str(format, false).map_err(invalid)?;
+    if !Multi::supports(&format) {
+        return Err(invalid(unsupported(module, &format)));
+    }
+    Ok((module, format))
+}
+
+/// The report of `format` of `module` unsupported by multi
+fn unsupported(module: Builtin, format: &Format) -> String {
+    let format = capabilities::name(format);
+    let module = module.name();
+    format!("The {format} format of {module} is not supported by multi")
+}
+
+struct MultiRunner {
+    modules: Modules,
+    line: Vec<u8>,
+}
+
+impl Runner for Multi {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        // The tagged lines are not JSON values even in JSON formats
+        monitor::run_text(monitor, &niri, format, config)
+    }
+}
+
+impl Multi {
+    /// Whether monitors may print their lines in `format` within multiplexed
+    /// output
+    pub(crate) fn supports(format: &Format) -> bool {
+        !matches!(
+            format,
+            Format::Jsonl | Format::Csv | Format::Yambar | Format::I3blocks
+        )
+    }
+
+    /// The monitor of multiplexed monitors configured by these options
+    ///
+    /// Exits with usage error if format of any monitor is not
+    /// [supported](Self::supports).
+    pub(crate) fn monitor(
+        self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        let mut modules = self
+            .modules
+            .iter()
+            .map(|module| (*module, format.clone()))
+            .collect::<Vec<_>>();
+        for (module, format) in self.formats {
+            match modules.iter_mut().find(|(m, _)| *m == module) {
+                Some((_, f)) => *f = format,
+                None => modules.push((module, format)),
+            }
+        }
+        if modules.is_empty() {
+            modules = [Builtin::Layout, Builtin::Workspaces, Builtin::Window]
+                .map(|module| (module, format.clone()))
+                .into();
+        }
+        for (module, format) in &modules {
+            if !Self::supports(format) {
+                exit::fail(Code::Usage, unsupported(*module, format));
+            }
+        }
+        MultiRunner {
+            modules: Modules::new(modules, config),
+            line: Vec::new(),
+        }
+    }
+}
+
+impl Monitor for MultiRunner {
+    fn apply(&mut self, event: Event) -> bool {
+        self.modules.apply(event)
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        self.modules.consistent(event)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        let mut rendered = false;
+        for (module, monitor) in self.modules.iter_mut() {
+            self.line.clear();
+            if !monitor.render(&mut self.line) {
+                continue;
+            }
+            if rendered {
+                out.push(b'\n');
+            }
+            out.extend_from_slice(module.name().as_bytes());
+            out.push(b' ');
+            out.extend_from_slice(&self.line);
+            rendered = true;
+        }
+        rendered
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        self.modules.queries()
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.modules.reload(config)
+    }
+}
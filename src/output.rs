@@ -0,0 +1,140 @@
+use crate::{
+    cache::Cache,
+    exit::{self, Code},
+    formatter::Formatter,
+    jsonl,
+};
+
+use std::{
+    io::{self, BufWriter, Write},
+    mem,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+/// The writer of monitor lines to stdout which never blocks the caller
+///
+/// The lines are written by background thread. While stdout is not writable
+/// (e.g. the consumer is frozen), the pending line is replaced by each new
+/// one, so only the latest state is written once stdout becomes writable
+/// again. The lines as emitted, before wrapping and formatting, are also
+/// stored to `cache` if given.
+///
+/// Stdout is buffered and flushed after each `flush_every` lines. With `jsonl`
+/// the lines are wrapped into timestamped records, as is if they are `json`
+/// and as strings otherwise. With `formatter` the records are passed
+/// through it and its replies are written instead.
+///
+/// The line buffers are swapped between the caller and the writer thread, so
+/// no allocations happen once buffers are grown to the size of lines.
+pub struct Output {
+    pending: Arc<(Mutex<Pending>, Condvar)>,
+    records: bool,
+    json: bool,
+}
+
+/// The line waiting to be written
+#[derive(Default)]
+struct Pending {
+    line: Vec<u8>,
+    /// The line as emitted, for the cache
+    raw: Vec<u8>,
+    ready: bool,
+}
+
+impl Output {
+    /// Start writer thread
+    pub fn new(
+        cache: Option<Cache>,
+        flush_every: u64,
+        jsonl: bool,
+        json: bool,
+        mut formatter: Option<Formatter>,
+    ) -> Self {
+        let records = jsonl || formatter.is_some();
+        let pending =
+            Arc::new((Mutex::new(Pending::default()), Condvar::new()));
+        let shared = pending.clone();
+        thread::spawn(move || {
+            let (lock, cvar) = &*shared;
+            let mut cached =
+                cache.as_ref().and_then(Cache::read).unwrap_or_default();
+            let mut line = Vec::new();
+            let mut raw = Vec::new();
+            let mut stdout = BufWriter::new(io::stdout());
+            let mut unflushed = 0;
+            loop {
+                {
+                    let mut pending = lock.lock().unwrap();
+                    while !pending.ready {
+                        pending = cvar.wait(pending).unwrap();
+                    }
+                    mem::swap(&mut pending.line, &mut line);
+                    mem::swap(&mut pending.raw, &mut raw);
+                    pending.ready = false;
+                }
+                if let Some(formatter) = &mut formatter {
+                    if let Err(e) = formatter.format(&mut line) {
+                        let message = format!("Failed to format output: {e}");
+                        exit::fail(Code::BrokenPipe, message);
+                    }
+                }
+                unflushed += 1;
+                let flush = unflushed >= flush_every;
+                if flush {
+                    unflushed = 0;
+                }
+                if let Err(e) = write_record(&mut stdout, &line, flush) {
+                    let message = format!("Failed to write output: {e}");
+                    exit::fail(Code::BrokenPipe, message);
+                }
+                if let Some(cache) = &cache {
+                    if cached != raw {
+                        // The cache is optional, so failures are ignored
+                        let _ = cache.write(&raw);
+                        cached.clone_from(&raw);
+                    }
+                }
+            }
+        });
+        Self {
+            pending,
+            records,
+            json,
+        }
+    }
+
+    /// Schedule line of change `kind` to be written, replacing not yet
+    /// written one
+    pub fn emit(&self, line: &[u8], kind: &str) {
+        let (lock, cvar) = &*self.pending;
+        let mut pending = lock.lock().unwrap();
+        pending.line.clear();
+        pending.raw.clear();
+        pending.raw.extend_from_slice(line);
+        if self.records && self.json {
+            jsonl::record(kind, line, &mut pending.line);
+        } else if self.records {
+            let text = String::from_utf8_lossy(line);
+            let data = serde_json::to_vec(&text).unwrap();
+            jsonl::record(kind, &data, &mut pending.line);
+        } else {
+            pending.line.extend_from_slice(line);
+        }
+        pending.ready = true;
+        cvar.notify_one();
+    }
+}
+
+fn write_record(
+    out: &mut impl Write,
+    line: &[u8],
+    flush: bool,
+) -> io::Result<()> {
+    out.write_all(line)?;
+    out.write_all(b"\n")?;
+    if flush {
+        out.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! The native formatter plugins.
+//!
+//! The plugin is a shared library exporting C function
+//!
+//! ```c
+//! int niri_glue_format(const uint8_t *record, size_t len,
+//!                      void (*emit)(void *ctx, const uint8_t *line,
+//!                                   size_t len),
+//!                      void *ctx);
+//! ```
+//!
+//! called for each record of monitor state, see [formatter](crate::formatter).
+//! The plugin passes the formatted line without newline to `emit` with the
+//! given `ctx` before returning, the buffers stay owned by their callers.
+//! Returning non-zero reports failure.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    io::{self, Error},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    slice,
+};
+
+#[link(name = "dl")]
+extern "C" {
+    fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlclose(handle: *mut c_void) -> c_int;
+    fn dlerror() -> *const c_char;
+}
+
+/// Resolve all symbols at once, so missing ones fail on load
+const RTLD_NOW: c_int = 2;
+
+/// The name of formatting function of plugins
+const ENTRY: &CStr = c"niri_glue_format";
+
+type Emit = extern "C" fn(ctx: *mut c_void, line: *const u8, len: usize);
+
+type Entry = unsafe extern "C" fn(
+    record: *const u8,
+    len: usize,
+    emit: Emit,
+    ctx: *mut c_void,
+) -> c_int;
+
+/// The loaded formatter plugin
+pub struct Plugin {
+    handle: *mut c_void,
+    entry: Entry,
+}
+
+// SAFETY: The plugin is used only by single thread at a time, plugins must
+// not rely on being called from the thread which loaded them
+unsafe impl Send for Plugin {}
+
+impl Plugin {
+    /// Load plugin from shared library at `path`
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let filename =
+            CString::new(path.as_os_str().as_bytes()).map_err(Error::other)?;
+        // SAFETY: The filename is valid C string. Loading runs constructors
+        // of library, which is the point of plugins.
+        let handle = unsafe { dlopen(filename.as_ptr(), RTLD_NOW) };
+        if handle.is_null() {
+            return Err(last_error());
+        }
+        // SAFETY: The handle is valid and the symbol name is C string
+        let symbol = unsafe { dlsym(handle, ENTRY.as_ptr()) };
+        if symbol.is_null() {
+            let e = last_error();
+            // SAFETY: The handle is valid and nothing refers the library
+            unsafe { dlclose(handle) };
+            return Err(e);
+        }
+        // SAFETY: The plugins must export the entry of this signature
+        let entry =
+            unsafe { std::mem::transmute::<*mut c_void, Entry>(symbol) };
+        Ok(Self { handle, entry })
+    }
+
+    /// Replace `record` with the line plugin produced for it
+    pub fn format(&mut self, record: &mut Vec<u8>) -> io::Result<()> {
+        let mut line = Vec::new();
+        let ctx = &mut line as *mut Vec<u8> as *mut c_void;
+        // SAFETY: The record is valid for the call and `ctx` is the buffer
+        // `emit` expects
+        let status =
+            unsafe { (self.entry)(record.as_ptr(), record.len(), emit, ctx) };
+        if status != 0 {
+            return Err(Error::other(format!("plugin failed with {status}")));
+        }
+        *record = line;
+        Ok(())
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and the entry is not used anymore
+        unsafe { dlclose(self.handle) };
+    }
+}
+
+/// Store the line passed by plugin into buffer `ctx`
+extern "C" fn emit(ctx: *mut c_void, line: *const u8, len: usize) {
+    // SAFETY: The `ctx` is the buffer passed to plugin by `format` and the
+    // line is valid for `len` bytes by contract of plugins
+    let (out, line) = unsafe {
+        (
+            &mut *(ctx as *mut Vec<u8>),
+            slice::from_raw_parts(line, len),
+        )
+    };
+    out.clear();
+    out.extend_from_slice(line);
+}
+
+/// The error reported by the last failed dl* call
+fn last_error() -> Error {
+    // SAFETY: The dlerror returns null or valid C string
+    let message = unsafe {
+        let message = dlerror();
+        if message.is_null() {
+            return Error::other("unknown error of dynamic loader");
+        }
+        CStr::from_ptr(message).to_string_lossy().into_owned()
+    };
+    Error::other(message)
+}
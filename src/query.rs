@@ -0,0 +1,246 @@
+//! One-shot and polling queries of niri state.
+
+use crate::{
+    exit::{self, Code},
+    jsonl,
+    sort::WindowSort,
+    waybar, yambar, Config, Format, Niri, Runner,
+};
+
+use clap::{Parser, ValueEnum};
+use niri_ipc::{Request, Response};
+use serde_json::Value;
+use std::{collections::BTreeSet, thread, time::Duration};
+
+/// The default interval of polling in watch mode
+const DEFAULT_INTERVAL: u64 = 1000;
+
+/// Niri state query.
+///
+/// Prints the queried part of niri state in chosen format. With `--watch`
+/// the query is repeated and the state is printed again on each change, for
+/// the state which has no events or for niri without event stream.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Query {
+    /// The part of state to query
+    target: Target,
+
+    /// Repeat query and print state on every change
+    #[arg(short, long)]
+    watch: bool,
+
+    /// The interval of watch polling in milliseconds
+    ///
+    /// The global `--poll-interval` is used by default.
+    #[arg(short, long)]
+    interval: Option<u64>,
+
+    /// The order of windows in windows query
+    ///
+    /// Without history of focus only the focused window goes first in
+    /// recency order.
+    #[arg(long, value_enum)]
+    sort: Option<WindowSort>,
+}
+
+/// The queryable parts of niri state
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Target {
+    Windows,
+    Workspaces,
+    Outputs,
+    FocusedWindow,
+    FocusedOutput,
+    KeyboardLayouts,
+    Version,
+}
+
+impl Target {
+    fn request(&self) -> Request {
+        match self {
+            Self::Windows => Request::Windows,
+            Self::Workspaces => Request::Workspaces,
+            Self::Outputs => Request::Outputs,
+            Self::FocusedWindow => Request::FocusedWindow,
+            Self::FocusedOutput => Request::FocusedOutput,
+            Self::KeyboardLayouts => Request::KeyboardLayouts,
+            Self::Version => Request::Version,
+        }
+    }
+}
+
+impl Runner for Query {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let interval = self
+            .interval
+            .or(config.poll_interval)
+            .unwrap_or(DEFAULT_INTERVAL);
+        if self.sort.is_some() && self.target != Target::Windows {
+            exit::fail(Code::Usage, "Only windows may be sorted");
+        }
+        let kind = self.target.to_possible_value().unwrap();
+        let mut last = None;
+        loop {
+            let reply = match self.sort {
+                Some(sort) => sorted_windows(&niri, sort),
+                None => send(&niri, self.target.request()),
+            };
+            if last.as_ref() != Some(&reply) {
+                print_reply(&reply, kind.get_name(), format.clone(), &config);
+                last = Some(reply);
+            }
+            if !self.watch {
+                break;
+            }
+            thread::sleep(Duration::from_millis(interval));
+        }
+    }
+}
+
+/// The windows in `sort` order
+fn sorted_windows(niri: &Niri, sort: WindowSort) -> Value {
+    let Response::Windows(mut windows) = niri.send(Request::Windows) else {
+        exit::fail(Code::Protocol, "Unexpected response to windows request");
+    };
+    let workspaces = match sort {
+        WindowSort::Spatial => match niri.send(Request::Workspaces) {
+            Response::Workspaces(workspaces) => workspaces,
+            _ => exit::fail(
+                Code::Protocol,
+                "Unexpected response to workspaces request",
+            ),
+        },
+        _ => Vec::new(),
+    };
+    let workspaces = workspaces.into_iter().map(|ws| (ws.id, ws)).collect();
+    let focused = windows.iter().find(|w| w.is_focused).map(|w| w.id);
+    sort.sort(&mut windows, &workspaces, focused.as_slice());
+    serde_json::to_value(windows).unwrap()
+}
+
+/// Send request and return the reply without the name of response type
+///
+/// Exits the process if niri rejects request.
+pub fn send(niri: &Niri, request: Request) -> Value {
+    let name = serde_json::to_string(&request).unwrap_or_default();
+    match niri.connect().send(request) {
+        Ok((Ok(response), _)) => reply(response),
+        Ok((Err(e), _)) => exit::fail(
+            Code::Rejected,
+            format!("Niri rejected request {name}: {e}"),
+        ),
+        Err(e) => exit::fail(
+            Code::of(&e).unwrap_or(Code::Protocol),
+            format!("Failed to send request {name}: {e}"),
+        ),
+    }
+}
+
+fn reply(response: Response) -> Value {
+    match serde_json::to_value(response).unwrap() {
+        Value::Object(object) if object.len() == 1 => {
+            object.into_iter().next().unwrap().1
+        }
+        reply => reply,
+    }
+}
+
+/// The text of string reply or JSON of other ones
+fn reply_text(reply: &Value) -> String {
+    match reply {
+        Value::String(text) => text.clone(),
+        reply => reply.to_string(),
+    }
+}
+
+/// Write reply as table with header
+///
+/// The rows are the items of list or map replies, or the reply itself. The
+/// columns are the fields of rows, the nested values are written as JSON.
+fn write_csv(reply: &Value, out: &mut Vec<u8>) {
+    let rows = match reply {
+        Value::Array(items) => items.iter().collect(),
+        // The outputs are mapped by their names, which are also their fields
+        Value::Object(map) if map.values().all(Value::is_object) => {
+            map.values().collect()
+        }
+        reply => vec![reply],
+    };
+    let objects = rows.iter().map(|row| row.as_object()).collect();
+    let Some(objects): Option<Vec<_>> = objects else {
+        // The replies like version have single value
+        out.extend_from_slice(b"value");
+        for row in rows {
+            out.push(b'\n');
+            out.extend_from_slice(csv_value(Some(row)).as_bytes());
+        }
+        return;
+    };
+    let columns = objects
+        .iter()
+        .flat_map(|fields| fields.keys())
+        .collect::<BTreeSet<_>>();
+    let header = columns.iter().map(|column| csv_cell(column));
+    out.extend_from_slice(header.collect::<Vec<_>>().join(",").as_bytes());
+    for fields in objects {
+        let cells = columns
+            .iter()
+            .map(|column| csv_value(fields.get(column.as_str())));
+        out.push(b'\n');
+        out.extend_from_slice(cells.collect::<Vec<_>>().join(",").as_bytes());
+    }
+}
+
+/// The CSV cell of value, empty for missing one
+fn csv_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => csv_cell(text),
+        Some(value) => csv_cell(&value.to_string()),
+    }
+}
+
+/// The CSV cell with `text`, quoted if needed
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.into()
+    }
+}
+
+/// Print reply of niri to `kind` of request to stdout in `format`
+pub fn print_reply(reply: &Value, kind: &str, format: Format, config: &Config) {
+    let mut out = Vec::new();
+    match format {
+        Format::Waybar
+        | Format::Ironbar
+        | Format::I3blocks
+        | Format::I3blocksJson => {
+            let markup = config.markup(&format);
+            let pretty = serde_json::to_string_pretty(reply).unwrap();
+            let message = waybar::Message {
+                text: markup.text(&reply_text(reply)),
+                tooltip: Some(markup.text(&pretty)),
+                class: vec![kind.into()],
+                ..Default::default()
+            };
+            message.write_as(&format, &mut out)
+        }
+        Format::Json | Format::Jsonl => {
+            serde_json::to_writer(&mut out, reply).unwrap()
+        }
+        Format::Csv => write_csv(reply, &mut out),
+        Format::Yambar => yambar::string(&mut out, kind, &reply_text(reply)),
+        Format::Xmobar | Format::Lemonbar | Format::Status => {
+            let text = config.markup(&format).text(&reply_text(reply));
+            out.extend_from_slice(text.as_bytes())
+        }
+    }
+    if format == Format::Jsonl {
+        jsonl::wrap(kind, &mut out);
+    }
+    out.push(b'\n');
+    exit::write_stdout(&out);
+}
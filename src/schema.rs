@@ -0,0 +1,275 @@
+//! The JSON Schemas of configuration file and of JSON outputs.
+
+use crate::{config::EVENTS, exit, Config, Format, Niri, Runner};
+
+use clap::{Parser, ValueEnum};
+use serde_json::{json, Value};
+
+/// JSON Schema printer.
+///
+/// Prints JSON Schema of configuration file, of messages of JSON based
+/// output formats or of json messages of each monitor, for editors and for
+/// validation by consumers. The data of jsonl records is any of messages.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Schema {
+    /// The document to print schema of
+    #[arg(value_enum, default_value = "config")]
+    target: Target,
+}
+
+/// The documents with schemas
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Target {
+    /// The configuration file
+    Config,
+    /// The messages of waybar format, also of json format of group
+    Waybar,
+    /// The records of jsonl format, also passed to `--formatter-cmd`
+    Jsonl,
+    /// The blocks of i3blocks-json format
+    I3blocksJson,
+    /// The json messages of layout monitor and of `switch-layout --print`
+    Layout,
+    /// The json messages of workspaces monitor and of pager
+    Workspaces,
+    /// The json messages of focused workspace monitor
+    FocusedWorkspace,
+    /// The json messages of windows monitor
+    Windows,
+    /// The json messages of focused window monitor
+    Window,
+    /// The json messages of status monitor
+    Status,
+}
+
+impl Runner for Schema {
+    fn run(self, _niri: Niri, _format: Format, _config: Config) {
+        let schema = match self.target {
+            Target::Config => config(),
+            Target::Jsonl => jsonl(),
+            target => {
+                let mut schema = message(target);
+                schema["$schema"] = DIALECT.into();
+                schema["title"] =
+                    format!("niri-glue {} message", name(target)).into();
+                schema
+            }
+        };
+        let schema = serde_json::to_string_pretty(&schema).unwrap();
+        exit::write_stdout(format!("{schema}\n").as_bytes());
+    }
+}
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn config() -> Value {
+    let formats: Vec<_> = Format::value_variants()
+        .iter()
+        .filter_map(|f| Some(f.to_possible_value()?.get_name().to_owned()))
+        .collect();
+    let count = json!({"type": "integer", "minimum": 0});
+    let glyphs = json!({
+        "type": "object",
+        "additionalProperties": {"type": "string"}
+    });
+    // The rewrite rules are added only if supported
+    #[cfg_attr(not(feature = "rewrite"), allow(unused_mut))]
+    let mut properties = json!({
+        "escape_markup": {"type": "boolean"},
+        "format": {"enum": formats},
+        "heartbeat": count,
+        "poll_interval": count,
+        "debounce": count,
+        "settle": count,
+        "flush_every": {"type": "integer", "minimum": 1},
+        "formatter_cmd": {"type": "string"},
+        "plugin": {"type": "string"},
+        "quiet": {"type": "boolean"},
+        "events": {"type": "array", "items": {"enum": EVENTS}},
+        "aliases": {
+            "type": "object",
+            "additionalProperties": {"type": "string"}
+        },
+        "app_names": {
+            "type": "object",
+            "additionalProperties": {"type": "string"}
+        },
+        "workspace_icons": {
+            "type": "object",
+            "properties": {
+                "all": glyphs,
+                "focused": glyphs,
+                "occupied": glyphs,
+                "empty": glyphs
+            },
+            "additionalProperties": false
+        },
+        "include": {"type": "array", "items": {"type": "string"}},
+        "hosts": {"type": "object", "additionalProperties": {"$ref": "#"}},
+        "profiles": {"type": "object", "additionalProperties": {"$ref": "#"}},
+    });
+    #[cfg(feature = "rewrite")]
+    {
+        properties["rewrite"] = json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "app_id": {"type": ["string", "null"]},
+                    "pattern": {"type": "string"},
+                    "replacement": {"type": "string"}
+                },
+                "required": ["pattern"],
+                "additionalProperties": false
+            }
+        });
+    }
+    json!({
+        "$schema": DIALECT,
+        "title": "niri-glue configuration",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false
+    })
+}
+
+/// The records of jsonl format with data of any of messages
+fn jsonl() -> Value {
+    let messages: Vec<_> = Target::value_variants()
+        .iter()
+        .filter(|target| !matches!(target, Target::Config | Target::Jsonl))
+        .map(|target| (name(*target), message(*target)))
+        .collect();
+    let refs: Vec<_> = messages
+        .iter()
+        .map(|(name, _)| json!({"$ref": format!("#/$defs/{name}")}))
+        .chain([json!({
+            "description": "The line of text based format",
+            "type": "string"
+        })])
+        .collect();
+    let defs: serde_json::Map<_, _> = messages.into_iter().collect();
+    json!({
+        "$schema": DIALECT,
+        "title": "niri-glue jsonl record",
+        "type": "object",
+        "properties": {
+            "time": {"type": "string", "format": "date-time"},
+            "type": {"type": "string"},
+            "data": {"anyOf": refs}
+        },
+        "required": ["time", "type", "data"],
+        "$defs": defs
+    })
+}
+
+/// The name of `target` on command line
+fn name(target: Target) -> String {
+    let value = target.to_possible_value().unwrap();
+    value.get_name().to_owned()
+}
+
+/// The schema of messages of `target`, without dialect and title
+fn message(target: Target) -> Value {
+    let string = json!({"type": "string"});
+    let id = json!({"type": "integer", "minimum": 0});
+    let optional =
+        |schema: &Value| json!({"anyOf": [schema, {"type": "null"}]});
+    match target {
+        Target::Config | Target::Jsonl => unreachable!("Not a message"),
+        Target::Waybar => json!({
+            "type": "object",
+            "properties": {
+                "text": string,
+                "alt": string,
+                "tooltip": string,
+                "class": {"type": "array", "items": string}
+            },
+            "required": ["text", "class"]
+        }),
+        Target::I3blocksJson => json!({
+            "type": "object",
+            "properties": {
+                "full_text": string,
+                "short_text": string,
+                "color": string
+            },
+            "required": ["full_text", "short_text"]
+        }),
+        Target::Layout => object(json!({"layout": string, "idx": id})),
+        Target::Workspaces => json!({
+            "type": "array",
+            "items": object(json!({
+                "id": id,
+                "idx": id,
+                "name": optional(&string),
+                "output": optional(&string),
+                "label": string,
+                "state": {"enum": ["focused", "active", "occupied", "empty"]},
+                "windows": id
+            }))
+        }),
+        Target::FocusedWorkspace => object(json!({
+            "name": optional(&string),
+            "idx": optional(&id),
+            "output": optional(&string)
+        })),
+        Target::Windows => {
+            let window = object(json!({
+                "id": id,
+                "key": string,
+                "title": string,
+                "app_id": string,
+                "app_name": string,
+                "workspace_id": optional(&id),
+                "focused": {"type": "boolean"},
+                "icon": optional(&string),
+                "icon_path": optional(&string)
+            }));
+            let app = object(json!({
+                "key": string,
+                "app_id": string,
+                "app_name": string,
+                "count": id,
+                "ids": {"type": "array", "items": id},
+                "focused": {"type": "boolean"},
+                "icon": optional(&string),
+                "icon_path": optional(&string)
+            }));
+            json!({
+                "description": "The windows, or applications if grouped",
+                "anyOf": [
+                    {"type": "array", "items": window},
+                    {"type": "array", "items": app}
+                ]
+            })
+        }
+        Target::Window => object(json!({
+            "title": string,
+            "id": optional(&id),
+            "key": optional(&string),
+            "app_id": optional(&string),
+            "app_name": optional(&string),
+            "workspace_id": optional(&id),
+            "workspace": optional(&string)
+        })),
+        Target::Status => object(json!({
+            "layout": string,
+            "workspace": string,
+            "title": string,
+            "text": string
+        })),
+    }
+}
+
+/// The object with all of `properties` and nothing else
+fn object(properties: Value) -> Value {
+    let required: Vec<_> = properties.as_object().unwrap().keys().collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false
+    })
+}
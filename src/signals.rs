@@ -0,0 +1,175 @@
+//! Minimal handling of POSIX signals.
+//!
+//! The signal handler only writes the signal number to a pipe, and the
+//! background thread reads the pipe and calls the user callback outside of
+//! signal context.
+//!
+//! The numbers of signals and flags are known for Linux only, where they
+//! differ between architectures. The signals can not be watched on other
+//! systems.
+
+use std::io;
+#[cfg(target_os = "linux")]
+use std::{
+    fs::File,
+    io::Read,
+    os::{fd::FromRawFd, raw::c_int},
+    sync::atomic::{AtomicI32, Ordering},
+    thread,
+};
+
+#[cfg(target_os = "linux")]
+extern "C" {
+    fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    fn pipe2(fds: *mut c_int, flags: c_int) -> c_int;
+    fn write(fd: c_int, buf: *const u8, count: usize) -> isize;
+}
+
+#[cfg(target_os = "linux")]
+const SIGHUP: c_int = 1;
+
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6"
+    )
+))]
+const SIGUSR1: c_int = 16;
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "sparc", target_arch = "sparc64")
+))]
+const SIGUSR1: c_int = 30;
+#[cfg(all(
+    target_os = "linux",
+    not(any(
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6",
+        target_arch = "sparc",
+        target_arch = "sparc64"
+    ))
+))]
+const SIGUSR1: c_int = 10;
+
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6"
+    )
+))]
+const SIGWINCH: c_int = 20;
+#[cfg(all(
+    target_os = "linux",
+    not(any(
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6"
+    ))
+))]
+const SIGWINCH: c_int = 28;
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "sparc", target_arch = "sparc64")
+))]
+const O_CLOEXEC: c_int = 0x400000;
+#[cfg(all(
+    target_os = "linux",
+    not(any(target_arch = "sparc", target_arch = "sparc64"))
+))]
+const O_CLOEXEC: c_int = 0o2000000;
+
+/// The write end of the pipe used by the signal handler
+#[cfg(target_os = "linux")]
+static PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// The signals handled by monitors and the switcher
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Signal {
+    /// Hangup, used to request reload of configuration
+    Hup,
+    /// User signal 1, used to request re-emission of state
+    Usr1,
+    /// Change of terminal size, used to redraw the switcher
+    Winch,
+}
+
+#[cfg(target_os = "linux")]
+impl Signal {
+    fn raw(self) -> c_int {
+        match self {
+            Self::Hup => SIGHUP,
+            Self::Usr1 => SIGUSR1,
+            Self::Winch => SIGWINCH,
+        }
+    }
+
+    fn from_raw(signum: u8) -> Option<Self> {
+        match c_int::from(signum) {
+            SIGHUP => Some(Self::Hup),
+            SIGUSR1 => Some(Self::Usr1),
+            SIGWINCH => Some(Self::Winch),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+extern "C" fn handler(signum: c_int) {
+    let fd = PIPE.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = signum as u8;
+        // SAFETY: write(2) is async-signal-safe and the buffer is valid
+        unsafe { write(fd, &byte, 1) };
+    }
+}
+
+/// Call `callback` from background thread on each of `signals` delivery
+///
+/// Must be called at most once per process. The pipe is not inherited by
+/// child processes.
+#[cfg(target_os = "linux")]
+pub fn watch(
+    signals: &[Signal],
+    mut callback: impl FnMut(Signal) + Send + 'static,
+) -> io::Result<()> {
+    let mut fds = [0 as c_int; 2];
+    // SAFETY: fds has room for two descriptors
+    if unsafe { pipe2(fds.as_mut_ptr(), O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the read end is owned by the file from now on
+    let mut reader = unsafe { File::from_raw_fd(fds[0]) };
+    PIPE.store(fds[1], Ordering::Relaxed);
+    for signum in signals {
+        // SAFETY: the handler is async-signal-safe
+        unsafe { signal(signum.raw(), handler) };
+    }
+    thread::spawn(move || {
+        let mut byte = [0u8; 1];
+        while reader.read_exact(&mut byte).is_ok() {
+            if let Some(signal) = Signal::from_raw(byte[0]) {
+                callback(signal);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Fail to watch signals, as their numbers are unknown on this system
+#[cfg(not(target_os = "linux"))]
+pub fn watch(
+    _signals: &[Signal],
+    _callback: impl FnMut(Signal) + Send + 'static,
+) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
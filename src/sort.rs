@@ -0,0 +1,48 @@
+//! The orders of window lists.
+
+use clap::ValueEnum;
+use niri_ipc::{Window, Workspace};
+use std::collections::HashMap;
+
+/// The order of windows, the opening order is kept for equal windows
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum WindowSort {
+    /// The most recently focused first
+    Recency,
+    /// By names of outputs and indices of workspaces
+    Spatial,
+    /// By application id
+    AppId,
+    /// By title
+    Title,
+}
+
+impl WindowSort {
+    /// Sort `windows` placed on `workspaces`
+    ///
+    /// The `recent` are ids of windows from the most recently focused one,
+    /// the rest of windows follow them.
+    pub fn sort(
+        self,
+        windows: &mut [Window],
+        workspaces: &HashMap<u64, Workspace>,
+        recent: &[u64],
+    ) {
+        windows.sort_by_key(|window| window.id);
+        match self {
+            Self::Recency => windows.sort_by_key(|window| {
+                recent
+                    .iter()
+                    .position(|id| *id == window.id)
+                    .unwrap_or(usize::MAX)
+            }),
+            Self::Spatial => windows.sort_by_key(|window| {
+                let ws = window.workspace_id.and_then(|id| workspaces.get(&id));
+                // The windows out of workspaces go last
+                (ws.is_none(), ws.map(|ws| (ws.output.clone(), ws.idx)))
+            }),
+            Self::AppId => windows.sort_by(|a, b| a.app_id.cmp(&b.app_id)),
+            Self::Title => windows.sort_by(|a, b| a.title.cmp(&b.title)),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    monitor::{self, Monitor, Query},
+    waybar, yambar, Config, FocusedWindow, FocusedWorkspace, Format, Layout,
+    Niri, Runner,
+};
+
+use clap::Parser;
+use niri_ipc::Event;
+use serde_json::{Map, Value};
+
+/// Status line monitor.
+///
+/// Produces to stdout single line combining keyboard layout, focused
+/// workspace and title of focused window on every change. The parts are
+/// rendered like `layout`, `focused-workspace` and `window` monitors do in
+/// `status` format.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Status {
+    /// Template of line
+    ///
+    /// The `{layout}`, `{workspace}` and `{title}` placeholders are replaced
+    /// by the corresponding parts.
+    #[arg(short, long, default_value = "{layout} {workspace} {title}")]
+    template: String,
+
+    #[command(flatten)]
+    layout: Layout,
+
+    #[command(flatten)]
+    window: FocusedWindow,
+}
+
+/// The part of status line rendered by its own monitor
+struct Part {
+    name: &'static str,
+    monitor: Box<dyn Monitor>,
+    text: String,
+}
+
+struct StatusRunner {
+    template: String,
+    format: Format,
+    markup: waybar::Markup,
+    parts: Vec<Part>,
+}
+
+impl Runner for Status {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+impl Status {
+    /// The monitor of status line configured by these options
+    pub(crate) fn monitor(
+        self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        let workspace = FocusedWorkspace::of(self.window.output());
+        let monitors: [(_, Box<dyn Monitor>); 3] = [
+            (
+                "layout",
+                Box::new(self.layout.monitor(Format::Status, config)),
+            ),
+            (
+                "workspace",
+                Box::new(workspace.monitor(Format::Status, config)),
+            ),
+            (
+                "title",
+                Box::new(self.window.monitor(Format::Status, config)),
+            ),
+        ];
+        let parts = monitors
+            .into_iter()
+            .map(|(name, monitor)| Part {
+                name,
+                monitor,
+                text: Default::default(),
+            })
+            .collect();
+        StatusRunner {
+            template: self.template,
+            markup: config.markup(&format),
+            format,
+            parts,
+        }
+    }
+}
+
+impl StatusRunner {
+    /// The template with placeholders replaced by texts of parts
+    fn line(&self) -> String {
+        self.parts.iter().fold(self.template.clone(), |line, part| {
+            line.replace(&format!("{{{}}}", part.name), &part.text)
+        })
+    }
+}
+
+impl Monitor for StatusRunner {
+    fn apply(&mut self, event: Event) -> bool {
+        let mut changed = false;
+        for part in &mut self.parts {
+            changed |= part.monitor.apply(event.clone());
+        }
+        changed
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        self.parts.iter().all(|part| part.monitor.consistent(event))
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        let mut text = Vec::new();
+        for part in &mut self.parts {
+            text.clear();
+            if !part.monitor.render(&mut text) {
+                text.clear();
+            }
+            part.text = String::from_utf8_lossy(&text).into_owned();
+        }
+        let line = self.markup.text(&self.line());
+        match self.format {
+            Format::Status | Format::Xmobar | Format::Lemonbar => {
+                out.extend_from_slice(line.as_bytes())
+            }
+            Format::Json | Format::Jsonl => {
+                let mut entry = Map::new();
+                for part in &self.parts {
+                    let text = self.markup.text(&part.text);
+                    entry.insert(part.name.into(), Value::String(text));
+                }
+                entry.insert("text".into(), Value::String(line));
+                serde_json::to_writer(out, &entry).unwrap()
+            }
+            Format::Yambar => {
+                for part in &self.parts {
+                    yambar::string(out, part.name, &part.text);
+                }
+                yambar::string(out, "status", &line);
+            }
+            Format::Csv => {
+                unreachable!("CSV format is rejected for this command")
+            }
+            Format::Waybar
+            | Format::Ironbar
+            | Format::I3blocks
+            | Format::I3blocksJson => {
+                let message = waybar::Message {
+                    text: line,
+                    class: vec!["status".into()],
+                    ..Default::default()
+                };
+                message.write_as(&self.format, out)
+            }
+        }
+        true
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        let mut queries = Vec::new();
+        for query in self.parts.iter().flat_map(|part| part.monitor.queries()) {
+            if !queries.contains(&query) {
+                queries.push(query);
+            }
+        }
+        queries
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.markup = config.markup(&self.format);
+        for part in &mut self.parts {
+            part.monitor.reload(config);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    apps::AppNames,
+    exit::{self, Code},
+    signals::{self, Signal},
+    text, Config, Format, Niri, Runner,
+};
+
+use clap::Parser;
+use niri_ipc::{Action, Request, Response, Window};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    process::Command,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+/// Interactive window switcher.
+///
+/// Lists windows in terminal with fuzzy search and focuses the selected one on
+/// Enter. Intended to be run inside floating terminal window.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Switch {}
+
+impl Runner for Switch {
+    fn run(self, niri: Niri, _format: Format, config: Config) {
+        let windows = match niri.send(Request::Windows) {
+            Response::Windows(windows) => windows,
+            _ => exit::fail(
+                Code::Protocol,
+                "Unexpected response to windows request",
+            ),
+        };
+        let selected = {
+            let mut terminal = Terminal::open().unwrap_or_else(|e| {
+                exit::fail(
+                    Code::Failure,
+                    format!("Failed to open terminal: {e}"),
+                )
+            });
+            SwitchRunner::new(windows, &AppNames::new(&config, &[]))
+                .run(&mut terminal)
+                .unwrap_or_else(|e| {
+                    exit::fail(
+                        Code::Failure,
+                        format!("Failed to use terminal: {e}"),
+                    )
+                })
+        };
+        if let Some(id) = selected {
+            niri.send(Request::Action(Action::FocusWindow { id }));
+        }
+    }
+}
+
+/// The key pressed by user
+enum Key {
+    Char(char),
+    Backspace,
+    ClearLine,
+    Enter,
+    Up,
+    Down,
+    Escape,
+    Unknown,
+}
+
+/// The input of switcher
+enum Input {
+    Key(Key),
+    /// The terminal has changed its size
+    Resize,
+}
+
+/// Controlling terminal switched to raw mode for the lifetime of the object
+struct Terminal {
+    tty: File,
+    saved: String,
+    /// The size as (rows, columns), updated on resize
+    size: (usize, usize),
+}
+
+impl Terminal {
+    fn open() -> io::Result<Self> {
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let saved = Self::stty(&tty, &["-g"])?;
+        Self::stty(&tty, &["raw", "-echo"])?;
+        let size = Self::query_size(&tty);
+        let mut terminal = Self { tty, saved, size };
+        // Switch to alternate screen and hide cursor
+        terminal.tty.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        Ok(terminal)
+    }
+
+    fn stty(tty: &File, args: &[&str]) -> io::Result<String> {
+        let output = Command::new("stty")
+            .args(args)
+            .stdin(tty.try_clone()?)
+            .output()?;
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(error.trim().to_owned()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+
+    /// Terminal size as (rows, columns)
+    fn query_size(tty: &File) -> (usize, usize) {
+        Self::stty(tty, &["size"])
+            .ok()
+            .and_then(|size| {
+                let (rows, cols) = size.split_once(' ')?;
+                Some((rows.parse().ok()?, cols.parse().ok()?))
+            })
+            .unwrap_or((24, 80))
+    }
+
+    /// Query the size again after the terminal was resized
+    fn resize(&mut self) {
+        self.size = Self::query_size(&self.tty);
+    }
+
+    /// The keys read in background thread together with resizes
+    ///
+    /// Without signals the size is not updated until restart.
+    fn inputs(&self) -> io::Result<Receiver<io::Result<Input>>> {
+        let (tx, inputs) = mpsc::channel();
+        let resize_tx = tx.clone();
+        let _ = signals::watch(&[Signal::Winch], move |_| {
+            let _ = resize_tx.send(Ok(Input::Resize));
+        });
+        let mut tty = self.tty.try_clone()?;
+        thread::spawn(move || loop {
+            let key = Self::read_key(&mut tty);
+            let failed = key.is_err();
+            if tx.send(key.map(Input::Key)).is_err() || failed {
+                break;
+            }
+        });
+        Ok(inputs)
+    }
+
+    fn read_key(tty: &mut File) -> io::Result<Key> {
+        let mut buf = [0u8; 32];
+        let len = tty.read(&mut buf)?;
+        let key = match &buf[..len] {
+            [] | [0x03] | [0x07] | [0x1b] => Key::Escape,
+            [b'\r'] | [b'\n'] => Key::Enter,
+            [0x7f] | [0x08] => Key::Backspace,
+            [0x15] => Key::ClearLine,
+            [0x10] | [0x1b, b'[', b'A'] | [0x1b, b'O', b'A'] => Key::Up,
+            [0x0e] | [0x1b, b'[', b'B'] | [0x1b, b'O', b'B'] => Key::Down,
+            [0x1b, ..] => Key::Unknown,
+            bytes => match String::from_utf8_lossy(bytes).chars().next() {
+                Some(c) if !c.is_control() => Key::Char(c),
+                _ => Key::Unknown,
+            },
+        };
+        Ok(key)
+    }
+}
+
+impl Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tty.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.tty.flush()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.tty.write_all(b"\x1b[?25h\x1b[?1049l");
+        let _ = Self::stty(&self.tty, &[&self.saved]);
+    }
+}
+
+struct SwitchRunner {
+    windows: Vec<Window>,
+    /// The lines describing windows in the list
+    lines: Vec<String>,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl SwitchRunner {
+    fn new(windows: Vec<Window>, names: &AppNames) -> Self {
+        let lines = windows.iter().map(|w| describe(w, names)).collect();
+        let mut runner = Self {
+            windows,
+            lines,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+        };
+        runner.filter();
+        runner
+    }
+
+    fn run(mut self, terminal: &mut Terminal) -> io::Result<Option<u64>> {
+        let inputs = terminal.inputs()?;
+        loop {
+            self.draw(terminal)?;
+            let input = inputs.recv().map_err(io::Error::other)??;
+            let key = match input {
+                Input::Key(key) => key,
+                Input::Resize => {
+                    terminal.resize();
+                    continue;
+                }
+            };
+            match key {
+                Key::Char(c) => {
+                    self.query.push(c);
+                    self.filter();
+                }
+                Key::Backspace => {
+                    self.query.pop();
+                    self.filter();
+                }
+                Key::ClearLine => {
+                    self.query.clear();
+                    self.filter();
+                }
+                Key::Up => self.selected = self.selected.saturating_sub(1),
+                Key::Down => {
+                    if self.selected + 1 < self.matches.len() {
+                        self.selected += 1;
+                    }
+                }
+                Key::Enter => {
+                    let selected = self.matches.get(self.selected);
+                    return Ok(selected.map(|&idx| self.windows[idx].id));
+                }
+                Key::Escape => return Ok(None),
+                Key::Unknown => (),
+            }
+        }
+    }
+
+    /// Recalculate the list of windows matched by query
+    fn filter(&mut self) {
+        let mut scored = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                fuzzy_score(&self.query, line).map(|s| (idx, s))
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|(_, a), (_, b)| b.cmp(a));
+        self.matches = scored.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+    }
+
+    fn draw(&self, terminal: &mut Terminal) -> io::Result<()> {
+        let (rows, cols) = terminal.size;
+        let mut screen = String::from("\x1b[H\x1b[2J");
+        let query = format!("> {}", self.query);
+        screen.push_str(&text::truncate(&query, cols, ""));
+        let height = rows.saturating_sub(1);
+        let skip = (self.selected + 1).saturating_sub(height);
+        let visible = self.matches.iter().enumerate().skip(skip).take(height);
+        for (line, &idx) in visible {
+            let width = cols.saturating_sub(2);
+            let text = text::truncate(&self.lines[idx], width, "");
+            if line == self.selected {
+                screen.push_str(&format!("\r\n\x1b[7m> {text}\x1b[0m"));
+            } else {
+                screen.push_str(&format!("\r\n  {text}"));
+            }
+        }
+        terminal.write_all(screen.as_bytes())?;
+        terminal.flush()
+    }
+}
+
+/// The line describing window in the list
+fn describe(window: &Window, names: &AppNames) -> String {
+    let title = window.title.as_deref().unwrap_or_default();
+    match window.app_id.as_deref() {
+        Some(app_id) => format!("{}: {title}", names.name(app_id)),
+        None => title.to_owned(),
+    }
+}
+
+/// Score of fuzzy match of `query` against `text`
+///
+/// Returns [None] if the characters of query are not found in text in the
+/// same order. The matches at the beginning of text and the consecutive
+/// matches have higher score.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut last = None;
+    let mut chars = text.chars().flat_map(char::to_lowercase).enumerate();
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let (pos, _) = chars.find(|&(_, c)| c == q)?;
+        score += match last {
+            Some(last) if last + 1 == pos => 10,
+            None if pos == 0 => 10,
+            _ => 1,
+        };
+        last = Some(pos);
+    }
+    Some(score * 100 - last.unwrap_or(0) as i64)
+}
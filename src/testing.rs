@@ -0,0 +1,175 @@
+//! The fixtures for byte-exact testing of monitor outputs.
+//!
+//! The canned events are pushed through the monitor chosen by command line
+//! arguments without connecting to niri, and the printed lines are compared
+//! with the snapshot file:
+//!
+//! ```
+//! use niri_glue::{testing, Config, Format};
+//!
+//! let config = Config::default();
+//! let args = ["layout", "--display", "short"];
+//! let events = testing::state(3);
+//! let lines = testing::render(&args, Format::Lemonbar, &config, events);
+//! assert_eq!(lines, ["%{A:niri-glue switch-layout:}US%{A}"]);
+//!
+//! let snapshot = std::env::temp_dir().join("niri-glue-layout.lemonbar");
+//! # let _ = std::fs::remove_file(&snapshot);
+//! // The first check writes the missing snapshot, the second compares it
+//! testing::assert_snapshot(&snapshot, &lines);
+//! testing::assert_snapshot(&snapshot, &lines);
+//! ```
+//!
+//! The missing snapshots are written, and with `NIRI_GLUE_UPDATE_SNAPSHOTS`
+//! set all of them are rewritten.
+
+use crate::{bench::Target, monitor::Monitor, Config, Format};
+
+use clap::Parser;
+use niri_ipc::{Event, KeyboardLayouts, Window, Workspace};
+use std::{env, fs, io, iter, path::Path};
+
+/// The variable making snapshots to be rewritten instead of checked
+const UPDATE: &str = "NIRI_GLUE_UPDATE_SNAPSHOTS";
+
+/// The monitor with its options as command line arguments
+#[derive(Parser, Debug)]
+struct Fixture {
+    #[command(subcommand)]
+    target: Target,
+}
+
+/// The events making up the state of two outputs with two workspaces each
+///
+/// The `windows` are spread over workspaces, the first one is focused.
+pub fn state(windows: u64) -> Vec<Event> {
+    let workspaces = (1..=4)
+        .map(|id| Workspace {
+            id,
+            idx: ((id - 1) % 2 + 1) as u8,
+            name: None,
+            output: Some(if id <= 2 { "DP-1" } else { "HDMI-A-1" }.into()),
+            is_active: id % 2 == 1,
+            is_focused: id == 1,
+            active_window_id: Some(id).filter(|id| *id <= windows),
+        })
+        .collect();
+    let windows = (1..=windows).map(|id| window(id, 0, id == 1)).collect();
+    let keyboard_layouts = KeyboardLayouts {
+        names: vec!["English (US)".into(), "Russian".into()],
+        current_idx: 0,
+    };
+    vec![
+        Event::WorkspacesChanged { workspaces },
+        Event::WindowsChanged { windows },
+        Event::KeyboardLayoutsChanged { keyboard_layouts },
+    ]
+}
+
+/// The `i`-th event of synthetic stream over [state] with `windows`
+///
+/// The stream cycles through focus changes, title changes, workspace
+/// switches and keyboard layout switches.
+pub fn event(i: usize, windows: u64) -> Event {
+    let id = i as u64 % windows.max(1) + 1;
+    match i % 4 {
+        0 => Event::WindowFocusChanged { id: Some(id) },
+        1 => Event::WindowOpenedOrChanged {
+            window: window(id, i, true),
+        },
+        2 => Event::WorkspaceActivated {
+            id: i as u64 % 4 + 1,
+            focused: true,
+        },
+        _ => Event::KeyboardLayoutSwitched { idx: (i % 2) as u8 },
+    }
+}
+
+/// The window `id` with title of given `generation`
+pub fn window(id: u64, generation: usize, is_focused: bool) -> Window {
+    Window {
+        id,
+        title: Some(format!("Window {id} — generation {generation}")),
+        app_id: Some(format!("org.example.App{}", id % 8)),
+        workspace_id: Some(id % 4 + 1),
+        is_focused,
+    }
+}
+
+/// The lines the monitor chosen by `args` prints in `format` for `events`
+///
+/// The `args` are the monitor command with its options, like
+/// `["layout", "--display", "short"]`. As when run, the line is taken only
+/// if it differs from the previous one. The inconsistent events, which would
+/// make monitor query niri, are skipped.
+///
+/// Panics if `args` are invalid or the line is not UTF-8.
+pub fn render(
+    args: &[&str],
+    format: Format,
+    config: &Config,
+    events: impl IntoIterator<Item = Event>,
+) -> Vec<String> {
+    let args = iter::once("niri-glue").chain(args.iter().copied());
+    let fixture = Fixture::try_parse_from(args)
+        .unwrap_or_else(|e| panic!("Invalid arguments of monitor: {e}"));
+    let mut monitor = fixture.target.monitor(format, config);
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut last = Vec::new();
+    for event in events {
+        if !monitor.consistent(&event) || !monitor.apply(event) {
+            continue;
+        }
+        line.clear();
+        if monitor.render(&mut line) && line != last {
+            let text = String::from_utf8(line.clone())
+                .unwrap_or_else(|e| panic!("Rendered line is not UTF-8: {e}"));
+            lines.push(text);
+            last.clone_from(&line);
+        }
+    }
+    lines
+}
+
+/// Check that `lines` match the snapshot at `path`, one per line
+///
+/// Panics with the first differing line on mismatch.
+pub fn assert_snapshot(path: impl AsRef<Path>, lines: &[String]) {
+    let path = path.as_ref();
+    let actual = lines.iter().map(|l| format!("{l}\n")).collect::<String>();
+    let expected = match fs::read_to_string(path) {
+        Ok(_) if env::var_os(UPDATE).is_some() => None,
+        Ok(expected) => Some(expected),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => panic!("Failed to read snapshot {}: {e}", path.display()),
+    };
+    let Some(expected) = expected else {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        return fs::write(path, actual).unwrap_or_else(|e| {
+            panic!("Failed to write snapshot {}: {e}", path.display())
+        });
+    };
+    if expected == actual {
+        return;
+    }
+    let (expected, actual) = (
+        expected.lines().collect::<Vec<_>>(),
+        actual.lines().collect::<Vec<_>>(),
+    );
+    let n = expected
+        .iter()
+        .zip(&actual)
+        .position(|(e, a)| e != a)
+        .unwrap_or(expected.len().min(actual.len()));
+    panic!(
+        "Output differs from snapshot {} at line {}:\n\
+         expected: {:?}\n  actual: {:?}",
+        path.display(),
+        n + 1,
+        expected.get(n),
+        actual.get(n),
+    );
+}
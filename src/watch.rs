@@ -0,0 +1,123 @@
+//! Minimal watching of files with inotify.
+//!
+//! The directory of file is watched rather than the file itself, so the
+//! file replaced by editors with rename is still tracked.
+
+use std::{
+    collections::HashSet,
+    ffi::CString,
+    fs::File,
+    io::{self, Read},
+    os::{
+        fd::{AsRawFd, FromRawFd},
+        raw::c_char,
+        raw::c_int,
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+extern "C" {
+    fn inotify_init1(flags: c_int) -> c_int;
+    fn inotify_add_watch(fd: c_int, path: *const c_char, mask: u32) -> c_int;
+}
+
+const IN_CLOEXEC: c_int = 0o2000000;
+const IN_CLOSE_WRITE: u32 = 0x8;
+const IN_MOVED_TO: u32 = 0x80;
+const IN_CREATE: u32 = 0x100;
+
+/// The size of `struct inotify_event` without name
+const EVENT_SIZE: usize = 16;
+
+/// The watched files as descriptors of watches of their directories with
+/// their names
+type Files = HashSet<(c_int, Vec<u8>)>;
+
+/// The set of files watched from background thread
+pub struct Watcher {
+    inotify: Arc<File>,
+    files: Arc<Mutex<Files>>,
+}
+
+impl Watcher {
+    /// Call `callback` from background thread each time any of watched
+    /// files is written or replaced
+    ///
+    /// No files are watched until they are [set](Watcher::set).
+    pub fn new(
+        mut callback: impl FnMut() + Send + 'static,
+    ) -> io::Result<Self> {
+        // SAFETY: inotify_init1 has no preconditions
+        let fd = unsafe { inotify_init1(IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the descriptor is owned by the file from now on
+        let inotify = Arc::new(unsafe { File::from_raw_fd(fd) });
+        let files = Arc::new(Mutex::new(Files::new()));
+        let (reader, watched) = (inotify.clone(), files.clone());
+        thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            while let Ok(len) = (&*reader).read(&mut buf) {
+                let files = watched.lock().unwrap();
+                if changed(&buf[..len], &files) {
+                    drop(files);
+                    callback();
+                }
+            }
+        });
+        Ok(Self { inotify, files })
+    }
+
+    /// Watch files at `paths` instead of previously watched ones
+    ///
+    /// The files in missing directories are skipped, as there is nothing to
+    /// watch yet.
+    pub fn set(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        let files = paths
+            .into_iter()
+            .filter_map(|path| self.add(&path).ok())
+            .collect();
+        *self.files.lock().unwrap() = files;
+    }
+
+    /// Watch the directory of file at `path`
+    fn add(&self, path: &Path) -> io::Result<(c_int, Vec<u8>)> {
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let dir = CString::new(dir.as_os_str().as_bytes())?;
+        let fd = self.inotify.as_raw_fd();
+        let mask = IN_CLOSE_WRITE | IN_MOVED_TO | IN_CREATE;
+        // SAFETY: the descriptor is inotify one and the path is C string. The
+        // same directory gets the same watch.
+        let wd = unsafe { inotify_add_watch(fd, dir.as_ptr(), mask) };
+        if wd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((wd, name.as_bytes().to_owned()))
+    }
+}
+
+/// Whether any of inotify events in `buf` refers any of `files`
+fn changed(mut buf: &[u8], files: &Files) -> bool {
+    let mut changed = false;
+    while buf.len() >= EVENT_SIZE {
+        let wd = c_int::from_ne_bytes(buf[0..4].try_into().unwrap());
+        let len = u32::from_ne_bytes(buf[12..16].try_into().unwrap()) as usize;
+        let end = (EVENT_SIZE + len).min(buf.len());
+        // The name is padded with zeroes
+        let name = buf[EVENT_SIZE..end].split(|b| *b == 0).next();
+        changed |= name.is_some_and(|name| files.contains(&(wd, name.into())));
+        buf = &buf[end..];
+    }
+    changed
+}
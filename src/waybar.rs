@@ -0,0 +1,134 @@
+//! The messages of waybar and markup of texts in them and in other bars.
+
+#[cfg(any(feature = "monitors", feature = "actions"))]
+use crate::{i3blocks::Block, Format};
+use crate::{lemonbar, xmobar};
+
+#[cfg(feature = "monitors")]
+use serde::Deserialize;
+#[cfg(any(feature = "monitors", feature = "actions"))]
+use serde::Serialize;
+
+/// The message of waybar custom module with `return-type` set to `json`
+#[cfg(any(feature = "monitors", feature = "actions"))]
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "monitors", derive(Deserialize))]
+pub struct Message {
+    /// The text shown in module
+    pub text: String,
+    /// The alternative text, used by waybar to choose format and icon
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
+    /// The text shown on hover
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+    /// The CSS classes of module
+    pub class: Vec<String>,
+}
+
+#[cfg(any(feature = "monitors", feature = "actions"))]
+impl Message {
+    /// Serialize message to single line appended to `out`
+    pub fn write(&self, out: &mut Vec<u8>) {
+        serde_json::to_writer(out, self).unwrap()
+    }
+
+    /// Write message appended to `out` in `format`
+    ///
+    /// Ironbar script modules get only the text of message, i3blocks ones
+    /// get the block with the text, other formats get the whole message.
+    pub fn write_as(&self, format: &Format, out: &mut Vec<u8>) {
+        match format {
+            Format::Ironbar => out.extend_from_slice(self.text.as_bytes()),
+            Format::I3blocks => Block::from(self).write(false, out),
+            Format::I3blocksJson => Block::from(self).write(true, out),
+            _ => self.write(out),
+        }
+    }
+}
+
+/// The policy of escaping texts placed within markup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Markup {
+    /// Whether the texts should be escaped
+    pub escape: bool,
+    /// The markup language the texts are placed within
+    pub syntax: Syntax,
+}
+
+/// The markup languages of emitted texts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Syntax {
+    /// The Pango markup of waybar and similar bars
+    Pango,
+    /// The tags of xmobar
+    Xmobar,
+    /// The formatting blocks of lemonbar
+    Lemonbar,
+    /// No markup, the focused items are marked by brackets
+    Plain,
+}
+
+impl Markup {
+    /// Escape text if escaping is enabled
+    pub fn text(&self, text: &str) -> String {
+        match (self.escape, self.syntax) {
+            (false, _) => text.to_owned(),
+            (true, Syntax::Pango) => escape(text),
+            (true, Syntax::Xmobar) => xmobar::escape(text),
+            (true, Syntax::Lemonbar) => lemonbar::escape(text),
+            (true, Syntax::Plain) => text.to_owned(),
+        }
+    }
+
+    /// Wrap text into markup coloring it with `color`, like `#ff0000`
+    #[cfg(feature = "monitors")]
+    pub fn color(&self, text: &str, color: &str) -> String {
+        match self.syntax {
+            Syntax::Pango if color.is_empty() => text.to_owned(),
+            Syntax::Pango => format!("<span color=\"{color}\">{text}</span>"),
+            Syntax::Xmobar => xmobar::color(text, color),
+            Syntax::Lemonbar => lemonbar::color(text, color),
+            Syntax::Plain => text.to_owned(),
+        }
+    }
+
+    /// Wrap text into markup drawing line under it
+    #[cfg(feature = "monitors")]
+    pub fn underline(&self, text: &str) -> String {
+        match self.syntax {
+            Syntax::Pango => format!("<u>{text}</u>"),
+            Syntax::Xmobar => xmobar::underline(text),
+            Syntax::Lemonbar => lemonbar::underline(text),
+            Syntax::Plain => format!("[{text}]"),
+        }
+    }
+
+    /// Wrap text into markup running `niri-glue` with `args` on click
+    ///
+    /// Pango and plain texts are not clickable, so the text is left as is.
+    #[cfg(any(feature = "monitors", feature = "actions"))]
+    pub fn action(&self, text: &str, args: &str) -> String {
+        match self.syntax {
+            Syntax::Pango | Syntax::Plain => text.to_owned(),
+            Syntax::Xmobar => xmobar::action(text, args),
+            Syntax::Lemonbar => lemonbar::action(text, args),
+        }
+    }
+}
+
+/// Escape text to be placed within Pango markup
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\'' => escaped.push_str("&#39;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,630 @@
+#[cfg(feature = "rewrite")]
+use crate::config::{Rewrite, RewriteRule};
+use crate::{
+    apps::AppNames,
+    cache,
+    icons::Icons,
+    layout::parse_key_val,
+    monitor::{self, Monitor, Query},
+    sort::WindowSort,
+    text, waybar, workspaces, yambar, Config, Format, Niri, Runner,
+};
+
+use clap::{Args, Parser};
+use niri_ipc::{
+    state::{EventStreamState, EventStreamStatePart},
+    Event, Window,
+};
+use serde::Serialize;
+#[cfg(feature = "rewrite")]
+use std::io::Error;
+use std::path::PathBuf;
+
+/// Windows monitor.
+///
+/// Produces to stdout the list of windows with their application icons on
+/// every change. Suitable for taskbars.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Windows {
+    /// Icon theme to look application icons up in
+    #[arg(long, default_value = "hicolor")]
+    icon_theme: String,
+
+    /// Show only windows on workspaces of the given output
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// The order of windows, the opening order by default
+    #[arg(long, value_enum)]
+    sort: Option<WindowSort>,
+
+    /// Show one entry per application with count and ids of its windows
+    ///
+    /// The applications are ordered by their first windows. Suitable for
+    /// dock-style taskbars.
+    #[arg(short, long)]
+    group_by_app: bool,
+
+    #[command(flatten)]
+    title: TitleFormat,
+}
+
+/// Focused window monitor.
+///
+/// Produces to stdout the title of focused window on every change.
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct FocusedWindow {
+    /// Show the active window of the given output instead of focused one
+    #[arg(short, long)]
+    output: Option<String>,
+
+    #[command(flatten)]
+    title: TitleFormat,
+}
+
+/// Options of window title display
+#[derive(Args, Debug, Clone)]
+struct TitleFormat {
+    /// Truncate titles longer than given count of columns
+    ///
+    /// The titles are cut between grapheme clusters, wide characters take
+    /// two columns. The clusters and widths are approximated by built-in
+    /// tables of Unicode 14.
+    #[arg(short, long)]
+    max_length: Option<usize>,
+
+    /// Text appended to truncated titles
+    #[arg(long, default_value = "…")]
+    ellipsis: String,
+
+    /// Display name of application
+    ///
+    /// Each argument must have format `APP_ID=NAME`. The names are taken
+    /// after ones from configuration file and the built-in ones.
+    #[arg(
+        long = "app-name",
+        value_name = "APP_ID=NAME",
+        value_parser = parse_key_val,
+        number_of_values = 1
+    )]
+    app_names: Vec<(String, String)>,
+
+    /// Names from configuration followed by ones from command line
+    #[arg(skip)]
+    names: AppNames,
+
+    /// Title rewrite rules
+    ///
+    /// Each argument must have format `APP_ID=PATTERN=REPLACEMENT`, where
+    /// APP_ID and PATTERN are regexes, REPLACEMENT may refer to groups of
+    /// PATTERN as `$1`. Empty APP_ID matches all applications. Rules are
+    /// applied in order after ones from configuration file.
+    #[cfg(feature = "rewrite")]
+    #[arg(short, long, value_parser = parse_rewrite, number_of_values = 1)]
+    rewrite: Vec<RewriteRule>,
+
+    /// Rules from configuration followed by ones from command line
+    #[cfg(feature = "rewrite")]
+    #[arg(skip)]
+    rules: Vec<RewriteRule>,
+}
+
+#[cfg(feature = "rewrite")]
+fn parse_rewrite(s: &str) -> Result<RewriteRule, Error> {
+    Rewrite::parse(s)?.compile()
+}
+
+impl TitleFormat {
+    /// Combine names and rewrite rules from configuration with command line
+    /// ones
+    fn configure(&mut self, config: &Config) {
+        self.names = AppNames::new(config, &self.app_names);
+        #[cfg(feature = "rewrite")]
+        {
+            self.rules = config
+                .rewrite
+                .iter()
+                .map(|rewrite| rewrite.compile().unwrap())
+                .chain(self.rewrite.iter().cloned())
+                .collect();
+        }
+    }
+
+    /// Rewrite title of window of application `app_id`
+    #[cfg(feature = "rewrite")]
+    fn rewrite(&self, app_id: &str, title: &str) -> String {
+        self.rules
+            .iter()
+            .fold(title.to_owned(), |title, rule| rule.apply(app_id, &title))
+    }
+
+    #[cfg(not(feature = "rewrite"))]
+    fn rewrite(&self, _app_id: &str, title: &str) -> String {
+        title.to_owned()
+    }
+
+    /// Truncate title to the maximum length
+    fn truncate(&self, title: &str) -> String {
+        match self.max_length {
+            Some(max) => text::truncate(title, max, &self.ellipsis),
+            None => title.to_owned(),
+        }
+    }
+
+    /// Rewrite title of window of application `app_id` and truncate it
+    fn apply(&self, app_id: &str, title: &str) -> String {
+        self.truncate(&self.rewrite(app_id, title))
+    }
+}
+
+/// The window entry of JSON output
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct Entry {
+    id: u64,
+    /// The key of entry kept while window exists, see [window_key]
+    key: String,
+    title: String,
+    app_id: String,
+    /// The display name of application
+    app_name: String,
+    workspace_id: Option<u64>,
+    focused: bool,
+    icon: Option<String>,
+    icon_path: Option<PathBuf>,
+}
+
+/// The application entry of JSON output of grouped windows
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct AppEntry {
+    /// The key of entry kept while application has windows, see [app_key]
+    key: String,
+    app_id: String,
+    app_name: String,
+    count: usize,
+    ids: Vec<u64>,
+    /// Whether any of windows is focused
+    focused: bool,
+    icon: Option<String>,
+    icon_path: Option<PathBuf>,
+}
+
+impl AppEntry {
+    /// The application name with count of windows if there are several
+    fn label(&self) -> String {
+        match self.count {
+            1 => self.app_name.clone(),
+            count => format!("{} ({count})", self.app_name),
+        }
+    }
+}
+
+/// The key of window `id` for widgets of bars, like eww `for` loops
+///
+/// The key does not change on updates of window, so the widget is kept
+/// instead of being re-created.
+fn window_key(id: u64) -> String {
+    format!("window-{id}")
+}
+
+/// The key of application `app_id` for widgets of bars
+///
+/// It is FNV-1a hash of app_id, which does not depend on the version of
+/// the tool.
+fn app_key(app_id: &str) -> String {
+    format!("app-{:016x}", cache::fnv(app_id.as_bytes()))
+}
+
+/// Group window entries by application
+fn group(entries: &[Entry]) -> Vec<AppEntry> {
+    let mut apps: Vec<AppEntry> = Vec::new();
+    for entry in entries {
+        match apps.iter_mut().find(|app| app.app_id == entry.app_id) {
+            Some(app) => {
+                app.count += 1;
+                app.ids.push(entry.id);
+                app.focused |= entry.focused;
+            }
+            None => apps.push(AppEntry {
+                key: app_key(&entry.app_id),
+                app_id: entry.app_id.clone(),
+                app_name: entry.app_name.clone(),
+                count: 1,
+                ids: vec![entry.id],
+                focused: entry.focused,
+                icon: entry.icon.clone(),
+                icon_path: entry.icon_path.clone(),
+            }),
+        }
+    }
+    apps
+}
+
+struct WindowsRunner {
+    config: Windows,
+    format: Format,
+    markup: waybar::Markup,
+    icons: Icons,
+    state: EventStreamState,
+    /// The ids of windows from the most recently focused one
+    recent: Vec<u64>,
+}
+
+impl Runner for Windows {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+impl Windows {
+    /// The monitor of windows configured by these options
+    pub(crate) fn monitor(
+        mut self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        self.title.configure(config);
+        WindowsRunner::new(self, format, config)
+    }
+}
+
+impl WindowsRunner {
+    fn new(config: Windows, format: Format, global: &Config) -> Self {
+        let icons = Icons::new(&config.icon_theme);
+        Self {
+            config,
+            markup: global.markup(&format),
+            format,
+            icons,
+            state: Default::default(),
+            recent: Vec::new(),
+        }
+    }
+
+    /// Move the focused window to the front of recent ones
+    fn track_focus(&mut self) {
+        let windows = &self.state.windows.windows;
+        if let Some(focused) = windows.values().find(|w| w.is_focused) {
+            if self.recent.first() != Some(&focused.id) {
+                self.recent.retain(|id| *id != focused.id);
+                self.recent.insert(0, focused.id);
+            }
+        }
+        if self.recent.len() > windows.len() {
+            self.recent.retain(|id| windows.contains_key(id));
+        }
+    }
+
+    /// Whether window belongs to the output chosen by user
+    fn on_output(&self, window: &Window) -> bool {
+        let Some(output) = self.config.output.as_deref() else {
+            return true;
+        };
+        window
+            .workspace_id
+            .and_then(|id| self.state.workspaces.workspaces.get(&id))
+            .is_some_and(|ws| ws.output.as_deref() == Some(output))
+    }
+
+    fn entries(&mut self) -> Vec<Entry> {
+        let mut windows = self
+            .state
+            .windows
+            .windows
+            .values()
+            .filter(|window| self.on_output(window))
+            .cloned()
+            .collect::<Vec<_>>();
+        match self.config.sort {
+            Some(sort) => {
+                let workspaces = &self.state.workspaces.workspaces;
+                sort.sort(&mut windows, workspaces, &self.recent)
+            }
+            None => windows.sort_by_key(|window| window.id),
+        }
+        windows
+            .into_iter()
+            .map(|window| {
+                let app_id = window.app_id.unwrap_or_default();
+                let icon = self.icons.lookup(&app_id);
+                let title = window.title.unwrap_or_default();
+                let title = self.config.title.apply(&app_id, &title);
+                let app_name = self.config.title.names.name(&app_id);
+                Entry {
+                    id: window.id,
+                    key: window_key(window.id),
+                    title: self.markup.text(&title),
+                    workspace_id: window.workspace_id,
+                    focused: window.is_focused,
+                    icon: icon.as_ref().map(|icon| icon.name.clone()),
+                    icon_path: icon.and_then(|icon| icon.path),
+                    app_id: self.markup.text(&app_id),
+                    app_name: self.markup.text(&app_name),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Monitor for WindowsRunner {
+    fn apply(&mut self, event: Event) -> bool {
+        let changed = self.state.apply(event).is_none();
+        self.track_focus();
+        changed
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        let layouts = self.state.keyboard_layouts.keyboard_layouts.as_ref();
+        monitor::known_workspaces(&self.state.workspaces, event)
+            && monitor::known_windows(&self.state.windows, event)
+            && monitor::known_layouts(layouts, event)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        let entries = self.entries();
+        let apps = self.config.group_by_app.then(|| group(&entries));
+        let labels: Vec<_> = match &apps {
+            Some(apps) => {
+                apps.iter().map(|app| (app.label(), app.focused)).collect()
+            }
+            None => entries
+                .iter()
+                .map(|entry| (entry.app_name.clone(), entry.focused))
+                .collect(),
+        };
+        match self.format {
+            Format::Waybar
+            | Format::Ironbar
+            | Format::I3blocks
+            | Format::I3blocksJson => {
+                waybar_message(&entries, &labels).write_as(&self.format, out)
+            }
+            Format::Xmobar | Format::Lemonbar | Format::Status => {
+                let text = tagged_text(&labels, self.markup);
+                out.extend_from_slice(text.as_bytes())
+            }
+            Format::Json | Format::Jsonl => match &apps {
+                Some(apps) => serde_json::to_writer(out, apps).unwrap(),
+                None => serde_json::to_writer(out, &entries).unwrap(),
+            },
+            Format::Yambar => {
+                let focused = entries.iter().find(|entry| entry.focused);
+                yambar::int(out, "count", entries.len() as i64);
+                let app_id = focused.map(|entry| entry.app_id.as_str());
+                yambar::string(out, "app_id", app_id.unwrap_or_default());
+                let app_name = focused.map(|entry| entry.app_name.as_str());
+                yambar::string(out, "app_name", app_name.unwrap_or_default());
+                let title = focused.map(|entry| entry.title.as_str());
+                yambar::string(out, "title", title.unwrap_or_default());
+            }
+            Format::Csv => {
+                unreachable!("CSV format is rejected for this command")
+            }
+        }
+        true
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        vec![Query::Workspaces, Query::Windows]
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.config.title.configure(config);
+        self.markup = config.markup(&self.format);
+    }
+}
+
+/// Waybar message with `labels` of windows in text and titles in tooltip
+///
+/// The labels are the texts with flag of focus. The entries are expected to
+/// be escaped already.
+fn waybar_message(
+    entries: &[Entry],
+    labels: &[(String, bool)],
+) -> waybar::Message {
+    let text = labels
+        .iter()
+        .map(|(label, focused)| {
+            if *focused {
+                format!("<b>{label}</b>")
+            } else {
+                label.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let tooltip = entries
+        .iter()
+        .map(|entry| format!("{}: {}", entry.app_name, entry.title))
+        .collect::<Vec<_>>()
+        .join("\n");
+    waybar::Message {
+        text,
+        alt: entries
+            .iter()
+            .find(|entry| entry.focused)
+            .and_then(|entry| entry.icon.clone()),
+        tooltip: Some(tooltip),
+        class: vec!["windows".into()],
+    }
+}
+
+/// The `labels` of windows with focused one underlined
+fn tagged_text(labels: &[(String, bool)], markup: waybar::Markup) -> String {
+    labels
+        .iter()
+        .map(|(label, focused)| {
+            if *focused {
+                markup.underline(label)
+            } else {
+                label.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The focused window entry of JSON output
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct FocusedEntry {
+    title: String,
+    id: Option<u64>,
+    /// The key of focused window, see [window_key]
+    key: Option<String>,
+    app_id: Option<String>,
+    /// The display name of application
+    app_name: Option<String>,
+    workspace_id: Option<u64>,
+    /// The name or index of workspace
+    workspace: Option<String>,
+}
+
+struct FocusedWindowRunner {
+    config: FocusedWindow,
+    format: Format,
+    markup: waybar::Markup,
+    state: EventStreamState,
+}
+
+impl Runner for FocusedWindow {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+impl FocusedWindow {
+    /// The output to show the active window of instead of focused one
+    pub(crate) fn output(&self) -> Option<String> {
+        self.output.clone()
+    }
+
+    /// The monitor of focused window configured by these options
+    pub(crate) fn monitor(
+        mut self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        self.title.configure(config);
+        FocusedWindowRunner::new(self, format, config)
+    }
+}
+
+impl FocusedWindowRunner {
+    fn new(config: FocusedWindow, format: Format, global: &Config) -> Self {
+        Self {
+            config,
+            markup: global.markup(&format),
+            format,
+            state: Default::default(),
+        }
+    }
+
+    /// The focused window or the active window of chosen output
+    fn window(&self) -> Option<&Window> {
+        let windows = &self.state.windows.windows;
+        match self.config.output.as_deref() {
+            None => windows.values().find(|window| window.is_focused),
+            Some(output) => self
+                .state
+                .workspaces
+                .workspaces
+                .values()
+                .find(|ws| ws.is_active && ws.output.as_deref() == Some(output))
+                .and_then(|ws| ws.active_window_id)
+                .and_then(|id| windows.get(&id)),
+        }
+    }
+}
+
+impl Monitor for FocusedWindowRunner {
+    fn apply(&mut self, event: Event) -> bool {
+        self.state.apply(event).is_none()
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        let layouts = self.state.keyboard_layouts.keyboard_layouts.as_ref();
+        monitor::known_workspaces(&self.state.workspaces, event)
+            && monitor::known_windows(&self.state.windows, event)
+            && monitor::known_layouts(layouts, event)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        let window = self.window();
+        let app_id = window.and_then(|w| w.app_id.as_deref());
+        let app_name =
+            app_id.map(|app_id| self.config.title.names.name(app_id));
+        let workspace_id = window.and_then(|w| w.workspace_id);
+        let title = window.and_then(|w| w.title.as_deref());
+        let title = self
+            .config
+            .title
+            .rewrite(app_id.unwrap_or_default(), title.unwrap_or_default());
+        let short = self.config.title.truncate(&title);
+        match self.format {
+            Format::Waybar
+            | Format::Ironbar
+            | Format::I3blocks
+            | Format::I3blocksJson => {
+                let mut class = vec!["window".to_owned()];
+                if title.is_empty() {
+                    class.push("empty".into());
+                }
+                let message = waybar::Message {
+                    text: self.markup.text(&short),
+                    alt: app_id.map(|app_id| self.markup.text(app_id)),
+                    tooltip: Some(self.markup.text(&title)),
+                    class,
+                };
+                message.write_as(&self.format, out)
+            }
+            Format::Xmobar | Format::Lemonbar | Format::Status => {
+                out.extend_from_slice(self.markup.text(&short).as_bytes())
+            }
+            Format::Json | Format::Jsonl => {
+                let workspace = workspace_id
+                    .and_then(|id| self.state.workspaces.workspaces.get(&id))
+                    .map(|ws| self.markup.text(&workspaces::label(ws)));
+                let entry = FocusedEntry {
+                    title: self.markup.text(&short),
+                    id: window.map(|w| w.id),
+                    key: window.map(|w| window_key(w.id)),
+                    app_id: app_id.map(|app_id| self.markup.text(app_id)),
+                    app_name: app_name.map(|name| self.markup.text(&name)),
+                    workspace_id,
+                    workspace,
+                };
+                serde_json::to_writer(out, &entry).unwrap()
+            }
+            Format::Yambar => {
+                let workspace = workspace_id
+                    .and_then(|id| self.state.workspaces.workspaces.get(&id))
+                    .map(workspaces::label);
+                yambar::string(out, "title", &short);
+                yambar::string(out, "app_id", app_id.unwrap_or_default());
+                yambar::string(out, "app_name", &app_name.unwrap_or_default());
+                yambar::string(
+                    out,
+                    "workspace",
+                    &workspace.unwrap_or_default(),
+                );
+                yambar::bool(out, "empty", title.is_empty());
+            }
+            Format::Csv => {
+                unreachable!("CSV format is rejected for this command")
+            }
+        }
+        true
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        vec![Query::Workspaces, Query::Windows]
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.config.title.configure(config);
+        self.markup = config.markup(&self.format);
+    }
+}
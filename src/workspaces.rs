@@ -0,0 +1,1042 @@
+#[cfg(feature = "monitors")]
+use crate::{
+    config::WorkspaceIcons,
+    layout::parse_key_val,
+    monitor::{self, Monitor, Query},
+    waybar, yambar,
+};
+#[cfg(feature = "actions")]
+use crate::{
+    exit::{self, Code},
+    ipc,
+};
+use crate::{Config, Format, Niri, Runner};
+
+use clap::Parser;
+#[cfg(feature = "actions")]
+use clap::ValueEnum;
+#[cfg(feature = "monitors")]
+use niri_ipc::{
+    state::{EventStreamState, EventStreamStatePart, WorkspacesState},
+    Event, Workspace,
+};
+#[cfg(feature = "actions")]
+use niri_ipc::{Action, Request, Response, WorkspaceReferenceArg};
+#[cfg(feature = "monitors")]
+use serde::Serialize;
+#[cfg(feature = "monitors")]
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "actions")]
+use std::{io::Error, str::FromStr};
+
+/// Workspace pager.
+///
+/// Produces to stdout compact grid of workspaces of each output on every
+/// change.
+#[cfg(feature = "monitors")]
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Pager {
+    /// Glyph of the focused workspace
+    #[arg(long, default_value = "●")]
+    focused: String,
+
+    /// Glyph of the active workspaces on unfocused outputs
+    #[arg(long, default_value = "◎")]
+    active: String,
+
+    /// Glyph of the workspaces with windows
+    #[arg(long, default_value = "◉")]
+    occupied: String,
+
+    /// Glyph of the workspaces without windows
+    #[arg(long, default_value = "○")]
+    empty: String,
+
+    /// Separator between outputs
+    #[arg(long, default_value = " ")]
+    separator: String,
+
+    /// Show only workspaces of the given output
+    #[arg(short, long)]
+    output: Option<String>,
+
+    #[command(flatten)]
+    icons: IconOptions,
+}
+
+/// Workspaces buttons monitor.
+///
+/// Produces to stdout single waybar message with one Pango span per
+/// workspace styled according to workspace state. Use together with
+/// `focus-workspace` command in `on-click` and `on-scroll-*` handlers.
+///
+/// The message class is `occupied` or `empty`, depending on windows of the
+/// focused workspace.
+///
+/// In xmobar and lemonbar formats the workspaces are colored instead and
+/// focused on click.
+#[cfg(feature = "monitors")]
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct Workspaces {
+    /// Span attributes of the focused workspace
+    #[arg(long, default_value = "weight=\"bold\" underline=\"single\"")]
+    focused_span: String,
+
+    /// Span attributes of the active workspaces on unfocused outputs
+    #[arg(long, default_value = "weight=\"bold\"")]
+    active_span: String,
+
+    /// Span attributes of the workspaces with windows
+    #[arg(long, default_value = "")]
+    occupied_span: String,
+
+    /// Span attributes of the workspaces without windows
+    #[arg(long, default_value = "alpha=\"50%\"")]
+    empty_span: String,
+
+    /// Color of the focused workspace in xmobar and lemonbar formats
+    ///
+    /// The focused workspace is underlined as well.
+    #[arg(long, default_value = "")]
+    focused_color: String,
+
+    /// Color of the active workspaces on unfocused outputs in xmobar and
+    /// lemonbar formats
+    #[arg(long, default_value = "")]
+    active_color: String,
+
+    /// Color of the workspaces with windows in xmobar and lemonbar formats
+    #[arg(long, default_value = "")]
+    occupied_color: String,
+
+    /// Color of the workspaces without windows in xmobar and lemonbar
+    /// formats
+    #[arg(long, default_value = "#808080")]
+    empty_color: String,
+
+    /// Separator between workspaces
+    #[arg(long, default_value = " ")]
+    separator: String,
+
+    /// Append count of windows to label of each occupied workspace
+    #[arg(long)]
+    counts: bool,
+
+    /// Show only workspaces of the given output
+    #[arg(short, long)]
+    output: Option<String>,
+
+    #[command(flatten)]
+    icons: IconOptions,
+}
+
+/// Options of workspace glyphs shown instead of labels
+#[cfg(feature = "monitors")]
+#[derive(clap::Args, Debug, Clone)]
+struct IconOptions {
+    /// Glyph of workspace in any state
+    ///
+    /// Each argument must have format `NAME=GLYPH`, where NAME is name or
+    /// index of workspace. The glyphs are taken after ones from
+    /// configuration file.
+    #[arg(
+        long = "icon",
+        value_name = "NAME=GLYPH",
+        value_parser = parse_key_val,
+        number_of_values = 1
+    )]
+    icons: Vec<(String, String)>,
+
+    /// Glyph of workspace when it is focused, in format of `--icon`
+    #[arg(
+        long = "focused-icon",
+        value_name = "NAME=GLYPH",
+        value_parser = parse_key_val,
+        number_of_values = 1
+    )]
+    focused_icons: Vec<(String, String)>,
+
+    /// Glyph of workspace when it has windows, in format of `--icon`
+    #[arg(
+        long = "occupied-icon",
+        value_name = "NAME=GLYPH",
+        value_parser = parse_key_val,
+        number_of_values = 1
+    )]
+    occupied_icons: Vec<(String, String)>,
+
+    /// Glyph of workspace when it has no windows, in format of `--icon`
+    #[arg(
+        long = "empty-icon",
+        value_name = "NAME=GLYPH",
+        value_parser = parse_key_val,
+        number_of_values = 1
+    )]
+    empty_icons: Vec<(String, String)>,
+
+    /// Glyphs from configuration followed by ones from command line
+    #[arg(skip)]
+    merged: WorkspaceIcons,
+}
+
+/// Focused workspace monitor.
+///
+/// Produces to stdout only the name or index of focused workspace and its
+/// output on every change.
+#[cfg(feature = "monitors")]
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct FocusedWorkspace {
+    /// Show the active workspace of the given output instead of focused one
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+/// Workspace focus switcher.
+///
+/// Focuses the workspace by index or name, or relative to the current one,
+/// or the workspace with given id.
+#[cfg(feature = "actions")]
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct FocusWorkspace {
+    /// Target workspace
+    ///
+    /// One of `next`, `prev`, `back` (previously focused workspace), index
+    /// on the focused output or name of workspace.
+    #[arg(required_unless_present = "id")]
+    target: Option<WorkspaceTarget>,
+
+    /// Id of workspace to focus, refers to it on any output and regardless
+    /// of its name
+    #[arg(long, conflicts_with = "target")]
+    id: Option<u64>,
+}
+
+/// Window mover.
+///
+/// Moves the focused window or the window with given id to the workspace by
+/// index or name, or relative to the current one.
+#[cfg(feature = "actions")]
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct MoveWindow {
+    /// Target workspace
+    ///
+    /// One of `next`, `prev`, index or name of workspace.
+    target: WorkspaceTarget,
+
+    /// Id of window to move, the focused window by default
+    #[arg(long)]
+    id: Option<u64>,
+}
+
+/// Workspace reorderer.
+///
+/// Moves the focused workspace up or down, or moves the focused workspace or
+/// the one by index or name to given index on its output. Suitable for
+/// drag-and-drop handlers of pagers.
+#[cfg(feature = "actions")]
+#[derive(Parser, Debug, Clone)]
+#[command(about, long_about)]
+pub struct MoveWorkspace {
+    /// Direction to move the focused workspace in
+    #[arg(
+        value_enum,
+        required_unless_present = "to_index",
+        conflicts_with = "to_index"
+    )]
+    direction: Option<Direction>,
+
+    /// Index starting from 1 to move workspace to, requires niri 25.01 or
+    /// newer
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    to_index: Option<u64>,
+
+    /// Index or name of workspace to move with `--to-index`, the focused
+    /// one by default
+    #[arg(long, requires = "to_index")]
+    workspace: Option<WorkspaceReferenceArg>,
+}
+
+/// The direction of workspace moving
+#[cfg(feature = "actions")]
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+}
+
+/// The state of workspace to display
+#[cfg(feature = "monitors")]
+enum WorkspaceState {
+    Focused,
+    Active,
+    Occupied,
+    Empty,
+}
+
+#[cfg(feature = "monitors")]
+impl WorkspaceState {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Focused => "focused",
+            Self::Active => "active",
+            Self::Occupied => "occupied",
+            Self::Empty => "empty",
+        }
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl From<&Workspace> for WorkspaceState {
+    fn from(ws: &Workspace) -> Self {
+        if ws.is_focused {
+            Self::Focused
+        } else if ws.is_active {
+            Self::Active
+        } else if ws.active_window_id.is_some() {
+            Self::Occupied
+        } else {
+            Self::Empty
+        }
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl IconOptions {
+    /// Combine glyphs from configuration with command line ones
+    fn configure(&mut self, config: &Config) {
+        self.merged = config.workspace_icons.clone();
+        self.merged.all.extend(self.icons.iter().cloned());
+        self.merged
+            .focused
+            .extend(self.focused_icons.iter().cloned());
+        self.merged
+            .occupied
+            .extend(self.occupied_icons.iter().cloned());
+        self.merged.empty.extend(self.empty_icons.iter().cloned());
+    }
+
+    /// The glyph of `ws` in its state, if any is set
+    ///
+    /// The glyph is looked up by name and then by index of workspace.
+    fn glyph(&self, ws: &Workspace) -> Option<&str> {
+        let icons = &self.merged;
+        let state = match WorkspaceState::from(ws) {
+            WorkspaceState::Focused => &icons.focused,
+            _ if ws.active_window_id.is_some() => &icons.occupied,
+            _ => &icons.empty,
+        };
+        let idx = ws.idx.to_string();
+        for glyphs in [state, &icons.all] {
+            let name = ws.name.as_ref().and_then(|name| glyphs.get(name));
+            if let Some(glyph) = name.or_else(|| glyphs.get(&idx)) {
+                return Some(glyph);
+            }
+        }
+        None
+    }
+
+    /// The glyph of `ws` or its label if no glyph is set
+    fn label(&self, ws: &Workspace) -> String {
+        self.glyph(ws).map_or_else(|| label(ws), str::to_owned)
+    }
+}
+
+/// The producer of message from workspaces state
+#[cfg(feature = "monitors")]
+trait Render {
+    /// Apply configuration, including reloaded one
+    fn configure(&mut self, config: &Config);
+
+    fn render(
+        &self,
+        state: &EventStreamState,
+        markup: waybar::Markup,
+    ) -> waybar::Message;
+
+    /// The text of bar markup with workspaces focused on click
+    fn tagged(
+        &self,
+        state: &EventStreamState,
+        markup: waybar::Markup,
+    ) -> String;
+
+    /// The only output to show workspaces of
+    fn only(&self) -> Option<&str>;
+
+    /// The label or glyph showing `ws` in JSON output
+    fn label(&self, ws: &Workspace) -> String;
+}
+
+#[cfg(feature = "monitors")]
+struct WorkspacesRunner<R: Render> {
+    config: R,
+    format: Format,
+    markup: waybar::Markup,
+    state: EventStreamState,
+}
+
+/// The workspace entry of JSON output
+#[cfg(feature = "monitors")]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct WorkspaceEntry {
+    id: u64,
+    idx: u8,
+    name: Option<String>,
+    output: Option<String>,
+    /// The label or glyph of workspace
+    label: String,
+    /// One of `focused`, `active`, `occupied` or `empty`
+    state: &'static str,
+    /// The count of windows on workspace
+    windows: usize,
+}
+
+/// The focused workspace entry of JSON output
+#[cfg(feature = "monitors")]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct FocusedEntry {
+    /// The name or index of workspace
+    name: Option<String>,
+    idx: Option<u8>,
+    output: Option<String>,
+}
+
+#[cfg(feature = "monitors")]
+struct FocusedWorkspaceRunner {
+    config: FocusedWorkspace,
+    format: Format,
+    markup: waybar::Markup,
+    state: WorkspacesState,
+}
+
+#[cfg(feature = "monitors")]
+impl Runner for Pager {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Runner for Workspaces {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Pager {
+    /// The monitor of workspaces configured by these options
+    pub(crate) fn monitor(
+        self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        WorkspacesRunner::new(self, format, config)
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Workspaces {
+    /// The monitor of workspaces configured by these options
+    pub(crate) fn monitor(
+        self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        WorkspacesRunner::new(self, format, config)
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl FocusedWorkspace {
+    /// The options showing the active workspace of `output`, or the focused
+    /// one if output is not set
+    pub(crate) fn of(output: Option<String>) -> Self {
+        Self { output }
+    }
+
+    /// The monitor of focused workspace configured by these options
+    pub(crate) fn monitor(
+        self,
+        format: Format,
+        config: &Config,
+    ) -> impl Monitor {
+        FocusedWorkspaceRunner {
+            config: self,
+            markup: config.markup(&format),
+            format,
+            state: Default::default(),
+        }
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Runner for FocusedWorkspace {
+    fn run(self, niri: Niri, format: Format, config: Config) {
+        let monitor = self.monitor(format.clone(), &config);
+        monitor::run(monitor, &niri, format, config)
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl<R: Render> WorkspacesRunner<R> {
+    fn new(config: R, format: Format, global: &Config) -> Self {
+        let mut config = config;
+        config.configure(global);
+        Self {
+            config,
+            markup: global.markup(&format),
+            format,
+            state: Default::default(),
+        }
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl<R: Render> Monitor for WorkspacesRunner<R> {
+    fn apply(&mut self, event: Event) -> bool {
+        self.state.apply(event).is_none()
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        let layouts = self.state.keyboard_layouts.keyboard_layouts.as_ref();
+        monitor::known_workspaces(&self.state.workspaces, event)
+            && monitor::known_windows(&self.state.windows, event)
+            && monitor::known_layouts(layouts, event)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        match self.format {
+            Format::Yambar => {
+                let only = self.config.only();
+                let outputs = by_output(&self.state.workspaces, only);
+                yambar_workspaces(outputs.values().flatten(), out);
+            }
+            Format::Json | Format::Jsonl => {
+                let counts = counts(&self.state);
+                let only = self.config.only();
+                let outputs = by_output(&self.state.workspaces, only);
+                let entries: Vec<_> = outputs
+                    .values()
+                    .flatten()
+                    .map(|ws| WorkspaceEntry {
+                        id: ws.id,
+                        idx: ws.idx,
+                        name: ws.name.as_deref().map(|n| self.markup.text(n)),
+                        output: ws.output.clone(),
+                        label: self.markup.text(&self.config.label(ws)),
+                        state: WorkspaceState::from(*ws).name(),
+                        windows: counts.get(&ws.id).copied().unwrap_or(0),
+                    })
+                    .collect();
+                serde_json::to_writer(out, &entries).unwrap()
+            }
+            Format::Xmobar | Format::Lemonbar | Format::Status => {
+                let text = self.config.tagged(&self.state, self.markup);
+                out.extend_from_slice(text.as_bytes());
+            }
+            _ => {
+                let message = self.config.render(&self.state, self.markup);
+                message.write_as(&self.format, out)
+            }
+        }
+        true
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        vec![Query::Workspaces, Query::Windows]
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.config.configure(config);
+        self.markup = config.markup(&self.format);
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl FocusedWorkspaceRunner {
+    /// The focused workspace or the active workspace of chosen output
+    fn workspace(&self) -> Option<&Workspace> {
+        let mut workspaces = self.state.workspaces.values();
+        match self.config.output.as_deref() {
+            None => workspaces.find(|ws| ws.is_focused),
+            Some(output) => workspaces.find(|ws| {
+                ws.is_active && ws.output.as_deref() == Some(output)
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Monitor for FocusedWorkspaceRunner {
+    fn apply(&mut self, event: Event) -> bool {
+        self.state.apply(event).is_none()
+    }
+
+    fn consistent(&self, event: &Event) -> bool {
+        monitor::known_workspaces(&self.state, event)
+    }
+
+    fn render(&mut self, out: &mut Vec<u8>) -> bool {
+        let workspace = self.workspace();
+        let name = workspace.map(|ws| self.markup.text(&label(ws)));
+        let output = workspace
+            .and_then(|ws| ws.output.as_deref())
+            .map(|output| self.markup.text(output));
+        match self.format {
+            Format::Waybar
+            | Format::Ironbar
+            | Format::I3blocks
+            | Format::I3blocksJson => {
+                let mut class = vec!["workspace".to_owned()];
+                if workspace.is_none() {
+                    class.push("empty".into());
+                }
+                let message = waybar::Message {
+                    text: name.unwrap_or_default(),
+                    tooltip: output,
+                    class,
+                    ..Default::default()
+                };
+                message.write_as(&self.format, out)
+            }
+            Format::Json | Format::Jsonl => {
+                let entry = FocusedEntry {
+                    name,
+                    idx: workspace.map(|ws| ws.idx),
+                    output,
+                };
+                serde_json::to_writer(out, &entry).unwrap()
+            }
+            Format::Xmobar | Format::Lemonbar | Format::Status => {
+                out.extend_from_slice(name.unwrap_or_default().as_bytes())
+            }
+            Format::Yambar => {
+                yambar::string(out, "name", &name.unwrap_or_default());
+                let idx = workspace.map_or(0, |ws| ws.idx);
+                yambar::int(out, "idx", idx.into());
+                yambar::string(out, "output", &output.unwrap_or_default());
+            }
+            Format::Csv => {
+                unreachable!("CSV format is rejected for this command")
+            }
+        }
+        true
+    }
+
+    fn queries(&self) -> Vec<Query> {
+        vec![Query::Workspaces]
+    }
+
+    fn reload(&mut self, config: &Config) {
+        self.markup = config.markup(&self.format);
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Pager {
+    fn glyph<'a>(&'a self, ws: &Workspace) -> &'a str {
+        if let Some(glyph) = self.icons.glyph(ws) {
+            return glyph;
+        }
+        match WorkspaceState::from(ws) {
+            WorkspaceState::Focused => &self.focused,
+            WorkspaceState::Active => &self.active,
+            WorkspaceState::Occupied => &self.occupied,
+            WorkspaceState::Empty => &self.empty,
+        }
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Render for Pager {
+    fn configure(&mut self, config: &Config) {
+        self.icons.configure(config);
+    }
+
+    fn only(&self) -> Option<&str> {
+        self.output.as_deref()
+    }
+
+    fn label(&self, ws: &Workspace) -> String {
+        self.glyph(ws).to_owned()
+    }
+
+    fn render(
+        &self,
+        state: &EventStreamState,
+        markup: waybar::Markup,
+    ) -> waybar::Message {
+        let outputs = by_output(&state.workspaces, self.output.as_deref());
+        let rows = outputs
+            .iter()
+            .map(|(output, workspaces)| {
+                let glyphs = workspaces.iter().map(|ws| self.glyph(ws));
+                (output, glyphs.collect::<String>())
+            })
+            .collect::<Vec<_>>();
+        let text = rows
+            .iter()
+            .map(|(_, glyphs)| glyphs.as_str())
+            .collect::<Vec<_>>()
+            .join(&self.separator);
+        let tooltip = rows
+            .iter()
+            .map(|(output, glyphs)| format!("{output}: {glyphs}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        waybar::Message {
+            text: markup.text(&text),
+            tooltip: Some(markup.text(&tooltip)),
+            class: vec!["pager".into()],
+            ..Default::default()
+        }
+    }
+
+    fn tagged(
+        &self,
+        state: &EventStreamState,
+        markup: waybar::Markup,
+    ) -> String {
+        let outputs = by_output(&state.workspaces, self.output.as_deref());
+        outputs
+            .values()
+            .map(|workspaces| {
+                let glyphs = workspaces.iter().map(|ws| {
+                    let glyph = markup.text(self.glyph(ws));
+                    markup.action(&glyph, &focus_args(ws))
+                });
+                glyphs.collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(&self.separator)
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Workspaces {
+    fn span(
+        &self,
+        ws: &Workspace,
+        count: usize,
+        markup: waybar::Markup,
+    ) -> String {
+        let attrs = match WorkspaceState::from(ws) {
+            WorkspaceState::Focused => &self.focused_span,
+            WorkspaceState::Active => &self.active_span,
+            WorkspaceState::Occupied => &self.occupied_span,
+            WorkspaceState::Empty => &self.empty_span,
+        };
+        let label = markup.text(&self.icons.label(ws));
+        if self.counts && count > 0 {
+            format!("<span {attrs}>{label}<sup>{count}</sup></span>")
+        } else {
+            format!("<span {attrs}>{label}</span>")
+        }
+    }
+}
+
+#[cfg(feature = "monitors")]
+impl Render for Workspaces {
+    fn configure(&mut self, config: &Config) {
+        self.icons.configure(config);
+    }
+
+    fn only(&self) -> Option<&str> {
+        self.output.as_deref()
+    }
+
+    fn label(&self, ws: &Workspace) -> String {
+        self.icons.label(ws)
+    }
+
+    fn render(
+        &self,
+        state: &EventStreamState,
+        markup: waybar::Markup,
+    ) -> waybar::Message {
+        let counts = counts(state);
+        let count = |ws: &Workspace| counts.get(&ws.id).copied().unwrap_or(0);
+        let outputs = by_output(&state.workspaces, self.output.as_deref());
+        let workspaces = outputs.values().flatten();
+        let text = workspaces
+            .clone()
+            .map(|ws| self.span(ws, count(ws), markup))
+            .collect::<Vec<_>>()
+            .join(&self.separator);
+        let tooltip = workspaces
+            .filter(|ws| ws.is_active)
+            .map(|ws| {
+                let output = ws.output.as_deref().unwrap_or_default();
+                format!("{output}: {}, windows: {}", label(ws), count(ws))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let focused = state
+            .workspaces
+            .workspaces
+            .values()
+            .find(|ws| ws.is_focused);
+        let occupancy = match focused.map(count) {
+            Some(0) | None => "empty",
+            Some(_) => "occupied",
+        };
+        waybar::Message {
+            text,
+            tooltip: Some(markup.text(&tooltip)),
+            class: vec!["workspaces".into(), occupancy.into()],
+            ..Default::default()
+        }
+    }
+
+    fn tagged(
+        &self,
+        state: &EventStreamState,
+        markup: waybar::Markup,
+    ) -> String {
+        let counts = counts(state);
+        let outputs = by_output(&state.workspaces, self.output.as_deref());
+        outputs
+            .values()
+            .flatten()
+            .map(|ws| {
+                let mut text = markup.text(&self.icons.label(ws));
+                let count = counts.get(&ws.id).copied().unwrap_or(0);
+                if self.counts && count > 0 {
+                    text = format!("{text}:{count}");
+                }
+                let state = WorkspaceState::from(*ws);
+                let color = match state {
+                    WorkspaceState::Focused => &self.focused_color,
+                    WorkspaceState::Active => &self.active_color,
+                    WorkspaceState::Occupied => &self.occupied_color,
+                    WorkspaceState::Empty => &self.empty_color,
+                };
+                text = markup.color(&text, color);
+                if let WorkspaceState::Focused = state {
+                    text = markup.underline(&text);
+                }
+                markup.action(&text, &focus_args(ws))
+            })
+            .collect::<Vec<_>>()
+            .join(&self.separator)
+    }
+}
+
+/// The target of workspace switching
+#[cfg(feature = "actions")]
+#[derive(Debug, Clone)]
+enum WorkspaceTarget {
+    Next,
+    Prev,
+    Back,
+    Reference(WorkspaceReferenceArg),
+}
+
+#[cfg(feature = "actions")]
+impl FromStr for WorkspaceTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "next" => Self::Next,
+            "prev" => Self::Prev,
+            "back" => Self::Back,
+            s => Self::Reference(s.parse().map_err(Error::other)?),
+        })
+    }
+}
+
+#[cfg(feature = "actions")]
+impl Runner for FocusWorkspace {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let target = match (self.target, self.id) {
+            (_, Some(id)) => {
+                WorkspaceTarget::Reference(WorkspaceReferenceArg::Id(id))
+            }
+            (Some(target), None) => target,
+            (None, None) => unreachable!("target is required without id"),
+        };
+        let action = match target {
+            WorkspaceTarget::Next => Action::FocusWorkspaceDown {},
+            WorkspaceTarget::Prev => Action::FocusWorkspaceUp {},
+            WorkspaceTarget::Back => Action::FocusWorkspacePrevious {},
+            WorkspaceTarget::Reference(reference) => {
+                Action::FocusWorkspace { reference }
+            }
+        };
+        niri.send(Request::Action(action));
+    }
+}
+
+#[cfg(feature = "actions")]
+impl Runner for MoveWindow {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        let action = match (self.target, self.id) {
+            (WorkspaceTarget::Back, _) => {
+                exit::fail(Code::Usage, "Moving window back is not supported")
+            }
+            (WorkspaceTarget::Next, None) => {
+                Action::MoveWindowToWorkspaceDown {}
+            }
+            (WorkspaceTarget::Prev, None) => Action::MoveWindowToWorkspaceUp {},
+            (WorkspaceTarget::Reference(reference), window_id) => {
+                Action::MoveWindowToWorkspace {
+                    window_id,
+                    reference,
+                }
+            }
+            (target, Some(id)) => {
+                let (step, side) = match target {
+                    WorkspaceTarget::Next => (1, "next"),
+                    _ => (-1, "previous"),
+                };
+                let reference =
+                    neighbour(&niri, id, step).unwrap_or_else(|| {
+                        exit::fail(
+                            Code::Failure,
+                            format!("No {side} workspace for window {id}"),
+                        )
+                    });
+                Action::MoveWindowToWorkspace {
+                    window_id: Some(id),
+                    reference,
+                }
+            }
+        };
+        niri.send(Request::Action(action));
+    }
+}
+
+#[cfg(feature = "actions")]
+impl Runner for MoveWorkspace {
+    fn run(self, niri: Niri, _format: Format, _config: Config) {
+        match (self.direction, self.to_index) {
+            (_, Some(index)) => {
+                niri.send_action(ipc::Action::MoveWorkspaceToIndex {
+                    index: index as usize,
+                    reference: self.workspace,
+                })
+            }
+            (Some(Direction::Up), None) => {
+                niri.send(Request::Action(Action::MoveWorkspaceUp {}));
+            }
+            (Some(Direction::Down), None) => {
+                niri.send(Request::Action(Action::MoveWorkspaceDown {}));
+            }
+            (None, None) => unreachable!("Direction or index is required"),
+        }
+    }
+}
+
+/// The workspace `step` positions away from the one of window `id` on the
+/// same output
+///
+/// Exits the process if there is no such window.
+#[cfg(feature = "actions")]
+fn neighbour(niri: &Niri, id: u64, step: i16) -> Option<WorkspaceReferenceArg> {
+    let Response::Windows(windows) = niri.send(Request::Windows) else {
+        exit::fail(Code::Protocol, "Unexpected response to windows request");
+    };
+    let Response::Workspaces(workspaces) = niri.send(Request::Workspaces)
+    else {
+        exit::fail(Code::Protocol, "Unexpected response to workspaces request");
+    };
+    let window = windows.iter().find(|window| window.id == id);
+    let window =
+        window.unwrap_or_else(|| exit::fail(Code::Failure, "No such window"));
+    let current = window
+        .workspace_id
+        .and_then(|id| workspaces.iter().find(|ws| ws.id == id))?;
+    let idx = i16::from(current.idx) + step;
+    workspaces
+        .iter()
+        .find(|ws| ws.output == current.output && i16::from(ws.idx) == idx)
+        .map(|ws| WorkspaceReferenceArg::Id(ws.id))
+}
+
+/// The name of workspace or its index if workspace is unnamed
+#[cfg(feature = "monitors")]
+pub fn label(ws: &Workspace) -> String {
+    ws.name.clone().unwrap_or_else(|| ws.idx.to_string())
+}
+
+/// The arguments of `focus-workspace` command focusing `ws`
+///
+/// The workspace is referred by id, which is the same on all outputs and
+/// needs no quoting.
+#[cfg(feature = "monitors")]
+fn focus_args(ws: &Workspace) -> String {
+    format!("focus-workspace --id {}", ws.id)
+}
+
+/// Append yambar tags of workspaces
+///
+/// The tags are `focused` with label of focused workspace, `count` of
+/// workspaces and `wsN` with label and `wsN_state` with state of `N`-th of
+/// workspaces, counting from 1.
+#[cfg(feature = "monitors")]
+fn yambar_workspaces<'a>(
+    workspaces: impl Iterator<Item = &'a &'a Workspace>,
+    out: &mut Vec<u8>,
+) {
+    let mut focused = String::new();
+    let mut count = 0;
+    for (i, ws) in workspaces.enumerate() {
+        let label = label(ws);
+        let state = WorkspaceState::from(*ws);
+        if let WorkspaceState::Focused = state {
+            focused = label.clone();
+        }
+        yambar::string(out, &format!("ws{}", i + 1), &label);
+        yambar::string(out, &format!("ws{}_state", i + 1), state.name());
+        count += 1;
+    }
+    yambar::string(out, "focused", &focused);
+    yambar::int(out, "count", count);
+}
+
+/// The count of windows on each workspace by its id
+#[cfg(feature = "monitors")]
+fn counts(state: &EventStreamState) -> HashMap<u64, usize> {
+    let mut counts = HashMap::new();
+    for window in state.windows.windows.values() {
+        if let Some(id) = window.workspace_id {
+            *counts.entry(id).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Workspaces grouped by output name and sorted by index
+///
+/// When `only` is set, the workspaces of other outputs are skipped.
+#[cfg(feature = "monitors")]
+fn by_output<'a>(
+    state: &'a WorkspacesState,
+    only: Option<&str>,
+) -> BTreeMap<&'a str, Vec<&'a Workspace>> {
+    let mut outputs = BTreeMap::<&str, Vec<&Workspace>>::new();
+    for ws in state.workspaces.values() {
+        let output = ws.output.as_deref().unwrap_or_default();
+        if only.is_some_and(|only| only != output) {
+            continue;
+        }
+        outputs.entry(output).or_default().push(ws);
+    }
+    for workspaces in outputs.values_mut() {
+        workspaces.sort_by_key(|ws| ws.idx);
+    }
+    outputs
+}
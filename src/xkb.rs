@@ -0,0 +1,106 @@
+//! The registry of xkb layouts.
+//!
+//! The registry is read from the `evdev.xml` rules file, the same source
+//! libxkbregistry uses.
+
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+/// The mapping between layout descriptions and codes
+///
+/// The codes have form `layout` or `layout(variant)`, like `us(intl)`.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    codes: HashMap<String, String>,
+    descriptions: HashMap<String, String>,
+}
+
+impl Registry {
+    /// Read the system registry
+    ///
+    /// The rules file is looked up in `$XKB_CONFIG_ROOT/rules` or in
+    /// `/usr/share/X11/xkb/rules`. The registry is empty if file is missing.
+    pub fn load() -> Self {
+        let root = env::var_os("XKB_CONFIG_ROOT")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/usr/share/X11/xkb"));
+        match fs::read_to_string(root.join("rules").join("evdev.xml")) {
+            Ok(xml) => Self::parse(&xml),
+            Err(_) => Default::default(),
+        }
+    }
+
+    fn parse(xml: &str) -> Self {
+        let mut registry = Self::default();
+        for layout in blocks(xml, "layout") {
+            let (variants, item) = match layout.find("<variantList>") {
+                Some(pos) => (&layout[pos..], &layout[..pos]),
+                None => ("", layout),
+            };
+            let Some((name, description)) = config_item(item) else {
+                continue;
+            };
+            registry.insert(description, name.clone());
+            for variant in blocks(variants, "variant") {
+                if let Some((variant, description)) = config_item(variant) {
+                    registry.insert(description, format!("{name}({variant})"));
+                }
+            }
+        }
+        registry
+    }
+
+    fn insert(&mut self, description: String, code: String) {
+        self.descriptions.insert(code.clone(), description.clone());
+        self.codes.insert(description, code);
+    }
+
+    /// The code of layout with `description`
+    pub fn code(&self, description: &str) -> Option<&str> {
+        self.codes.get(description).map(String::as_str)
+    }
+
+    /// The description of layout with `code`
+    #[cfg(feature = "actions")]
+    pub fn description(&self, code: &str) -> Option<&str> {
+        self.descriptions.get(code).map(String::as_str)
+    }
+}
+
+/// The contents of each `<tag>` element of `xml`, attributes are skipped
+fn blocks<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{tag}"), format!("</{tag}>"));
+    let mut blocks = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        if !rest.starts_with(['>', ' ']) {
+            continue;
+        }
+        let Some(start) = rest.find('>') else {
+            break;
+        };
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        blocks.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+    blocks
+}
+
+/// The name and description of the first `<configItem>` of `xml`
+fn config_item(xml: &str) -> Option<(String, String)> {
+    let item = *blocks(xml, "configItem").first()?;
+    let name = *blocks(item, "name").first()?;
+    let description = *blocks(item, "description").first()?;
+    Some((unescape(name.trim()), unescape(description.trim())))
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,42 @@
+//! The markup of xmobar texts.
+//!
+//! See the description of `<fc>`, `<box>`, `<action>` and `<raw>` tags in
+//! xmobar documentation.
+
+/// Escape text to be placed within xmobar markup
+///
+/// The text with tag characters is wrapped into `<raw>` tag, which shows it
+/// intact.
+pub fn escape(text: &str) -> String {
+    if text.contains('<') {
+        format!("<raw={}:{text}/>", text.chars().count())
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Wrap text into tag coloring it with `color`, like `#ff0000`
+///
+/// The text is left as is if color is empty.
+#[cfg(feature = "monitors")]
+pub fn color(text: &str, color: &str) -> String {
+    if color.is_empty() {
+        text.to_owned()
+    } else {
+        format!("<fc={color}>{text}</fc>")
+    }
+}
+
+/// Wrap text into tag drawing line under it
+#[cfg(feature = "monitors")]
+pub fn underline(text: &str) -> String {
+    format!("<box type=Bottom>{text}</box>")
+}
+
+/// Wrap text into tag running `niri-glue` with `args` on left click
+///
+/// The `niri-glue` is expected to be found in `PATH`.
+#[cfg(any(feature = "monitors", feature = "actions"))]
+pub fn action(text: &str, args: &str) -> String {
+    format!("<action=`niri-glue {args}`>{text}</action>")
+}
@@ -0,0 +1,22 @@
+//! The output of yambar script module.
+//!
+//! Each state is a transaction of `tag|type|value` lines terminated by empty
+//! line, see yambar-modules-script(5). The tags are appended to the line of
+//! output, so the line end written after it terminates the transaction.
+
+/// Append tag of string type, the line breaks are replaced by spaces
+pub fn string(out: &mut Vec<u8>, tag: &str, value: &str) {
+    let value = value.replace(['\n', '\r'], " ");
+    out.extend_from_slice(format!("{tag}|string|{value}\n").as_bytes());
+}
+
+/// Append tag of integer type
+pub fn int(out: &mut Vec<u8>, tag: &str, value: i64) {
+    out.extend_from_slice(format!("{tag}|int|{value}\n").as_bytes());
+}
+
+/// Append tag of boolean type
+#[cfg(feature = "monitors")]
+pub fn bool(out: &mut Vec<u8>, tag: &str, value: bool) {
+    out.extend_from_slice(format!("{tag}|bool|{value}\n").as_bytes());
+}